@@ -0,0 +1,3 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX authors_name_trgm_idx ON authors USING GIN (name gin_trgm_ops);
@@ -0,0 +1,12 @@
+-- Keep in sync with the MAX_*_LENGTH constants in src/validations/mod.rs
+ALTER TABLE authors
+  ADD CONSTRAINT authors_name_length CHECK (char_length(name) <= 256),
+  ADD CONSTRAINT authors_nationality_length CHECK (char_length(nationality) <= 80);
+
+ALTER TABLE books
+  ADD CONSTRAINT books_title_length CHECK (char_length(title) <= 256),
+  ADD CONSTRAINT books_genre_length CHECK (char_length(genre) <= 80);
+
+ALTER TABLE users
+  ADD CONSTRAINT users_name_length CHECK (char_length(name) <= 256),
+  ADD CONSTRAINT users_email_length CHECK (char_length(email) <= 90);
@@ -0,0 +1,14 @@
+ALTER TABLE books ADD COLUMN updated_at timestamptz NOT NULL DEFAULT now();
+UPDATE books SET updated_at = created_at;
+
+-- Keeps Last-Modified correct for every UPDATE, including bulk admin ones.
+CREATE FUNCTION set_updated_at() RETURNS trigger AS $$
+BEGIN
+  NEW.updated_at = now();
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER books_set_updated_at
+  BEFORE UPDATE ON books
+  FOR EACH ROW EXECUTE FUNCTION set_updated_at();
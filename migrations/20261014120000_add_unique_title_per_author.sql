@@ -0,0 +1,4 @@
+-- Titles are compared case-insensitively, so "Dune" and "dune" by the same
+-- author count as the same book. Fails if duplicates already exist; clean
+-- them up before migrating.
+CREATE UNIQUE INDEX books_title_author_unique ON books (lower(title), author_id);
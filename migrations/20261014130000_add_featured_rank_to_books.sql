@@ -0,0 +1,5 @@
+-- Position in the homepage rail; NULL means the book isn't featured.
+ALTER TABLE books ADD COLUMN featured_rank INT NULL;
+
+CREATE UNIQUE INDEX books_featured_rank_unique ON books (featured_rank)
+  WHERE featured_rank IS NOT NULL;
@@ -0,0 +1,10 @@
+CREATE TABLE author_aliases(
+  id uuid DEFAULT gen_random_uuid() NOT NULL,
+  PRIMARY KEY (id),
+  author_id uuid NOT NULL REFERENCES authors(id) ON DELETE CASCADE,
+  alias TEXT NOT NULL CONSTRAINT author_aliases_alias_length CHECK (char_length(alias) <= 256),
+  created_at timestamptz NOT NULL
+);
+
+CREATE UNIQUE INDEX author_aliases_author_alias_unique ON author_aliases (author_id, lower(alias));
+CREATE INDEX author_aliases_alias_trgm_idx ON author_aliases USING GIN (alias gin_trgm_ops);
@@ -0,0 +1,13 @@
+-- An edition points at the book it is an edition of; NULL for an original
+-- work. Editions are one level deep, which create_book enforces. Deleting
+-- the original turns its editions into standalone books.
+ALTER TABLE books ADD COLUMN parent_book_id UUID NULL REFERENCES books (id) ON DELETE SET NULL;
+
+CREATE INDEX books_parent_book_id_idx ON books (parent_book_id)
+  WHERE parent_book_id IS NOT NULL;
+
+-- Editions usually share their original's title, so only original works
+-- have to be unique per author.
+DROP INDEX books_title_author_unique;
+CREATE UNIQUE INDEX books_title_author_unique ON books (lower(title), author_id)
+  WHERE parent_book_id IS NULL;
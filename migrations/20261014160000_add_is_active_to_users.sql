@@ -0,0 +1,3 @@
+-- Deactivated users keep their row, and everything linked to it, instead
+-- of being deleted.
+ALTER TABLE users ADD COLUMN is_active BOOLEAN NOT NULL DEFAULT true;
@@ -0,0 +1,4 @@
+-- Expression indexes for GET /books/search. The search query must use the
+-- exact same to_tsvector('english', ...) expressions to hit them.
+CREATE INDEX books_title_search_idx ON books USING GIN (to_tsvector('english', title));
+CREATE INDEX authors_name_search_idx ON authors USING GIN (to_tsvector('english', name));
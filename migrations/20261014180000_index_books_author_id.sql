@@ -0,0 +1,4 @@
+-- fk_books_authors has been there since books got an author, but nothing
+-- indexed it: listing an author's books and deleting an author both
+-- scanned the whole table.
+CREATE INDEX books_author_id_idx ON books (author_id);
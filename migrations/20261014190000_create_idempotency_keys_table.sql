@@ -0,0 +1,12 @@
+-- One row per Idempotency-Key and route. response_status stays NULL while
+-- the first request is still running.
+CREATE TABLE idempotency_keys(
+  key TEXT NOT NULL CONSTRAINT idempotency_keys_key_length CHECK (char_length(key) <= 255),
+  scope TEXT NOT NULL,
+  PRIMARY KEY (key, scope),
+  request_body BYTEA NOT NULL,
+  response_status SMALLINT,
+  response_headers TEXT[],
+  response_body BYTEA,
+  created_at timestamptz NOT NULL
+);
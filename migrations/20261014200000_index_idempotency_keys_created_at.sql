@@ -0,0 +1,2 @@
+-- Lets the periodic purge find expired keys without scanning the table.
+CREATE INDEX idempotency_keys_created_at_idx ON idempotency_keys (created_at);
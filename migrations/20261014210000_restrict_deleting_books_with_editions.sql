@@ -0,0 +1,6 @@
+-- Turning editions into standalone books on delete could give an author
+-- two original works with the same title, which books_title_author_unique
+-- rejects. A work with editions now has to lose them before it goes.
+ALTER TABLE books DROP CONSTRAINT books_parent_book_id_fkey;
+ALTER TABLE books ADD CONSTRAINT books_parent_book_id_fkey
+  FOREIGN KEY (parent_book_id) REFERENCES books (id) ON DELETE RESTRICT;
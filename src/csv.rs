@@ -0,0 +1,139 @@
+use actix_web::{http::header, HttpRequest};
+
+pub const CSV_CONTENT_TYPE: &str = "text/csv";
+
+pub fn accepts_csv(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .split(',')
+                .any(|media_type| media_type.trim().starts_with(CSV_CONTENT_TYPE))
+        })
+        .unwrap_or(false)
+}
+
+pub fn csv_row(fields: &[&str]) -> String {
+    let mut row = fields
+        .iter()
+        .map(|field| escape_field(field))
+        .collect::<Vec<String>>()
+        .join(",");
+    row.push('\n');
+    row
+}
+
+fn escape_field(field: &str) -> String {
+    let needs_quoting = field.contains([',', '"', '\n', '\r']);
+
+    if needs_quoting {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Splits CSV text into records, undoing the quoting done by `csv_row`.
+/// Blank lines are skipped.
+pub fn parse_csv(input: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\r', false) if chars.peek() == Some(&'\n') => {}
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                let finished = std::mem::take(&mut record);
+                if finished.iter().any(|field| !field.is_empty()) {
+                    records.push(finished);
+                }
+            }
+            (c, _) => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(String::from("Unterminated quoted field."));
+    }
+    record.push(field);
+    if record.iter().any(|field| !field.is_empty()) {
+        records.push(record);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn plain_fields() {
+        assert_eq!(csv_row(&["Dune", "Frank Herbert"]), "Dune,Frank Herbert\n");
+    }
+
+    #[test]
+    fn field_with_comma() {
+        assert_eq!(csv_row(&["Hello, World"]), "\"Hello, World\"\n");
+    }
+
+    #[test]
+    fn field_with_quotes() {
+        assert_eq!(
+            csv_row(&[r#"The "Best" Book"#]),
+            "\"The \"\"Best\"\" Book\"\n"
+        );
+    }
+
+    #[test]
+    fn field_with_newline() {
+        assert_eq!(csv_row(&["line\nbreak"]), "\"line\nbreak\"\n");
+    }
+
+    #[test]
+    fn parse_round_trips_escaped_fields() {
+        let fields = ["Hello, World", r#"The "Best" Book"#, "line\nbreak", ""];
+        assert_eq!(
+            parse_csv(&csv_row(&fields)).unwrap(),
+            vec![fields.map(String::from).to_vec()]
+        );
+    }
+
+    #[test]
+    fn parse_handles_crlf_and_blank_lines() {
+        assert_eq!(
+            parse_csv("title,genre\r\n\r\nDune,Sci-Fi").unwrap(),
+            vec![vec!["title", "genre"], vec!["Dune", "Sci-Fi"]]
+        );
+    }
+
+    #[test]
+    fn parse_rejects_unterminated_quote() {
+        assert!(parse_csv("\"Dune,Sci-Fi\n").is_err());
+    }
+
+    #[test]
+    fn detects_csv_accept_header() {
+        let csv = TestRequest::default()
+            .insert_header((header::ACCEPT, "text/csv; charset=utf-8"))
+            .to_http_request();
+        let json = TestRequest::default()
+            .insert_header((header::ACCEPT, "application/json"))
+            .to_http_request();
+
+        assert!(accepts_csv(&csv));
+        assert!(!accepts_csv(&json));
+    }
+}
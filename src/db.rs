@@ -0,0 +1,615 @@
+use futures_util::future::BoxFuture;
+use futures_util::stream::BoxStream;
+use futures_util::TryStreamExt;
+use sqlx::pool::PoolConnection;
+use sqlx::postgres::{PgQueryResult, PgRow, PgStatement, PgTypeInfo};
+use sqlx::{Describe, Either, Execute, Executor, PgPool, Postgres, Transaction};
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+pub struct ReadPool(pub TimedPool);
+
+pub struct WritePool(pub TimedPool);
+
+/// A `PgPool` that records how long every connection acquire waits, for
+/// plain reads as well as transactions, and warns about every statement
+/// slower than the slow query threshold, streamed ones included. Queries
+/// run on it the same way as on the pool itself.
+#[derive(Clone, Debug)]
+pub struct TimedPool(PgPool);
+
+impl TimedPool {
+    pub fn new(pool: PgPool) -> Self {
+        Self(pool)
+    }
+
+    pub async fn acquire(&self) -> Result<PoolConnection<Postgres>, sqlx::Error> {
+        self.acquire_for(None).await
+    }
+
+    /// The pool, reporting slow statements and acquires under `operation`.
+    /// For statements that run outside `retrying` and `in_transaction`,
+    /// which name their own.
+    pub fn named(&self, operation: &'static str) -> NamedPool<'_> {
+        NamedPool {
+            pool: self,
+            operation,
+        }
+    }
+
+    async fn acquire_for(
+        &self,
+        operation: Option<&'static str>,
+    ) -> Result<PoolConnection<Postgres>, sqlx::Error> {
+        let start = Instant::now();
+        let connection = self.0.acquire().await;
+        record_acquire(operation, start.elapsed());
+        connection
+    }
+
+    fn fetch_many_for<'e, 'q: 'e, E>(
+        &self,
+        operation: Option<&'static str>,
+        query: E,
+    ) -> BoxStream<'e, Result<Either<PgQueryResult, PgRow>, sqlx::Error>>
+    where
+        E: Execute<'q, Postgres> + 'q,
+    {
+        let pool = self.clone();
+
+        Box::pin(async_stream::try_stream! {
+            let mut connection = pool.acquire_for(operation).await?;
+            let sql = query.sql();
+            let mut rows = timed_rows(operation, sql, connection.fetch_many(query));
+            while let Some(row) = rows.try_next().await? {
+                yield row;
+            }
+        })
+    }
+
+    fn fetch_optional_for<'e, 'q: 'e, E>(
+        &self,
+        operation: Option<&'static str>,
+        query: E,
+    ) -> BoxFuture<'e, Result<Option<PgRow>, sqlx::Error>>
+    where
+        E: Execute<'q, Postgres> + 'q,
+    {
+        let pool = self.clone();
+
+        Box::pin(async move {
+            let mut connection = pool.acquire_for(operation).await?;
+            let sql = query.sql();
+            timed_row(operation, sql, connection.fetch_optional(query)).await
+        })
+    }
+}
+
+impl Deref for TimedPool {
+    type Target = PgPool;
+
+    fn deref(&self) -> &PgPool {
+        &self.0
+    }
+}
+
+impl<'p> Executor<'p> for &'_ TimedPool {
+    type Database = Postgres;
+
+    fn fetch_many<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> BoxStream<'e, Result<Either<PgQueryResult, PgRow>, sqlx::Error>>
+    where
+        E: Execute<'q, Postgres> + 'q,
+    {
+        self.fetch_many_for(None, query)
+    }
+
+    fn fetch_optional<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> BoxFuture<'e, Result<Option<PgRow>, sqlx::Error>>
+    where
+        E: Execute<'q, Postgres> + 'q,
+    {
+        self.fetch_optional_for(None, query)
+    }
+
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [PgTypeInfo],
+    ) -> BoxFuture<'e, Result<PgStatement<'q>, sqlx::Error>> {
+        self.0.prepare_with(sql, parameters)
+    }
+
+    fn describe<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+    ) -> BoxFuture<'e, Result<Describe<Postgres>, sqlx::Error>> {
+        self.0.describe(sql)
+    }
+}
+
+/// A `TimedPool` from `TimedPool::named`.
+#[derive(Clone, Copy, Debug)]
+pub struct NamedPool<'p> {
+    pool: &'p TimedPool,
+    operation: &'static str,
+}
+
+impl<'p> Executor<'p> for NamedPool<'_> {
+    type Database = Postgres;
+
+    fn fetch_many<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> BoxStream<'e, Result<Either<PgQueryResult, PgRow>, sqlx::Error>>
+    where
+        E: Execute<'q, Postgres> + 'q,
+    {
+        self.pool.fetch_many_for(Some(self.operation), query)
+    }
+
+    fn fetch_optional<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> BoxFuture<'e, Result<Option<PgRow>, sqlx::Error>>
+    where
+        E: Execute<'q, Postgres> + 'q,
+    {
+        self.pool.fetch_optional_for(Some(self.operation), query)
+    }
+
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [PgTypeInfo],
+    ) -> BoxFuture<'e, Result<PgStatement<'q>, sqlx::Error>> {
+        self.pool.0.prepare_with(sql, parameters)
+    }
+
+    fn describe<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+    ) -> BoxFuture<'e, Result<Describe<Postgres>, sqlx::Error>> {
+        self.pool.0.describe(sql)
+    }
+}
+
+/// A transaction from `begin`, whose statements are timed like the ones
+/// run on a `TimedPool` and reported under the operation that began it.
+/// Queries run on `&mut transaction`.
+#[derive(Debug)]
+pub struct TimedTransaction {
+    transaction: Transaction<'static, Postgres>,
+    operation: &'static str,
+}
+
+impl TimedTransaction {
+    pub async fn commit(self) -> Result<(), sqlx::Error> {
+        self.transaction.commit().await
+    }
+
+    pub async fn rollback(self) -> Result<(), sqlx::Error> {
+        self.transaction.rollback().await
+    }
+}
+
+impl<'t> Executor<'t> for &'t mut TimedTransaction {
+    type Database = Postgres;
+
+    fn fetch_many<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> BoxStream<'e, Result<Either<PgQueryResult, PgRow>, sqlx::Error>>
+    where
+        't: 'e,
+        E: Execute<'q, Postgres> + 'q,
+    {
+        let sql = query.sql();
+        timed_rows(
+            Some(self.operation),
+            sql,
+            self.transaction.fetch_many(query),
+        )
+    }
+
+    fn fetch_optional<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> BoxFuture<'e, Result<Option<PgRow>, sqlx::Error>>
+    where
+        't: 'e,
+        E: Execute<'q, Postgres> + 'q,
+    {
+        let sql = query.sql();
+        Box::pin(timed_row(
+            Some(self.operation),
+            sql,
+            self.transaction.fetch_optional(query),
+        ))
+    }
+
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [PgTypeInfo],
+    ) -> BoxFuture<'e, Result<PgStatement<'q>, sqlx::Error>>
+    where
+        't: 'e,
+    {
+        self.transaction.prepare_with(sql, parameters)
+    }
+
+    fn describe<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+    ) -> BoxFuture<'e, Result<Describe<Postgres>, sqlx::Error>>
+    where
+        't: 'e,
+    {
+        self.transaction.describe(sql)
+    }
+}
+
+// Only the time spent waiting on the database counts, not the time the
+// caller takes between rows, so a slow client reading a streamed export
+// doesn't make its query look slow.
+fn timed_rows<'e, T: Send + 'e>(
+    operation: Option<&'static str>,
+    sql: &'e str,
+    mut rows: BoxStream<'e, Result<T, sqlx::Error>>,
+) -> BoxStream<'e, Result<T, sqlx::Error>> {
+    Box::pin(async_stream::try_stream! {
+        let mut waited = Duration::ZERO;
+        loop {
+            let start = Instant::now();
+            let row = rows.try_next().await;
+            waited += start.elapsed();
+            match row {
+                Ok(Some(row)) => yield row,
+                Ok(None) => break,
+                Err(e) => {
+                    record_query(operation, sql, waited);
+                    Err(e)?;
+                }
+            }
+        }
+        record_query(operation, sql, waited);
+    })
+}
+
+async fn timed_row<F, T>(operation: Option<&'static str>, sql: &str, query: F) -> T
+where
+    F: Future<Output = T>,
+{
+    let start = Instant::now();
+    let output = query.await;
+    record_query(operation, sql, start.elapsed());
+    output
+}
+
+// Statements on a plain `TimedPool` run inside the `db_query` span that
+// `retrying` opens, which is where this event picks up the operation name;
+// the others carry it themselves.
+fn record_query(operation: Option<&'static str>, sql: &str, elapsed: Duration) {
+    if elapsed >= slow_query_threshold() {
+        tracing::warn!(
+            operation,
+            statement = sql.split_whitespace().collect::<Vec<_>>().join(" "),
+            duration_ms = elapsed.as_millis() as u64,
+            "Slow database query"
+        );
+    }
+}
+
+static SLOW_QUERY_THRESHOLD_MS: AtomicU64 = AtomicU64::new(500);
+
+pub fn set_slow_query_threshold(threshold: Duration) {
+    SLOW_QUERY_THRESHOLD_MS.store(threshold.as_millis() as u64, Ordering::Relaxed);
+}
+
+pub fn slow_query_threshold() -> Duration {
+    Duration::from_millis(SLOW_QUERY_THRESHOLD_MS.load(Ordering::Relaxed))
+}
+
+static ACQUIRE_WARN_THRESHOLD_MS: AtomicU64 = AtomicU64::new(100);
+
+pub fn set_acquire_warn_threshold(threshold: Duration) {
+    ACQUIRE_WARN_THRESHOLD_MS.store(threshold.as_millis() as u64, Ordering::Relaxed);
+}
+
+pub fn acquire_warn_threshold() -> Duration {
+    Duration::from_millis(ACQUIRE_WARN_THRESHOLD_MS.load(Ordering::Relaxed))
+}
+
+static MAX_RETRIES: AtomicU32 = AtomicU32::new(2);
+static RETRY_BASE_DELAY_MS: AtomicU64 = AtomicU64::new(20);
+
+pub fn set_retry_policy(max_retries: u32, base_delay: Duration) {
+    MAX_RETRIES.store(max_retries, Ordering::Relaxed);
+    RETRY_BASE_DELAY_MS.store(base_delay.as_millis() as u64, Ordering::Relaxed);
+}
+
+// Nothing has run yet when a transaction starts, so a failure to get a
+// connection is always safe to retry. Each attempt records its own acquire
+// time; the backoff between them is not counted.
+pub async fn begin(
+    operation: &'static str,
+    pool: &TimedPool,
+) -> Result<TimedTransaction, sqlx::Error> {
+    retrying(operation, || async {
+        let transaction = Transaction::begin(pool.acquire().await?).await?;
+        Ok(TimedTransaction {
+            transaction,
+            operation,
+        })
+    })
+    .await
+}
+
+/// Errors worth running the same statement again for: lost or unavailable
+/// connections. Serialization failures and deadlocks are left out; they
+/// abort the whole transaction, which `in_transaction` runs again instead.
+pub fn is_transient(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Database(e) => e.code().is_some_and(|code| code.starts_with("08")),
+        sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut => true,
+        _ => false,
+    }
+}
+
+/// Serialization failures and deadlocks: Postgres rolled the transaction
+/// back, and running it again from the start can succeed.
+pub fn is_conflict(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Database(e) => e
+            .code()
+            .is_some_and(|code| code == "40001" || code == "40P01"),
+        _ => false,
+    }
+}
+
+/// Lets `in_transaction` find the database error behind a handler's own
+/// error type.
+pub trait DatabaseCause {
+    fn database_cause(&self) -> Option<&sqlx::Error>;
+}
+
+impl DatabaseCause for sqlx::Error {
+    fn database_cause(&self) -> Option<&sqlx::Error> {
+        Some(self)
+    }
+}
+
+/// Runs `body` on a fresh transaction from `begin`, and runs all of it
+/// again, after a jittered backoff, while it fails with a conflict. `body`
+/// owns the transaction and commits it, so a conflict reported at commit is
+/// retried too. Anything it does outside the transaction runs once per
+/// attempt.
+pub async fn in_transaction<F, Fut, T, E>(
+    operation: &'static str,
+    pool: &TimedPool,
+    body: F,
+) -> Result<T, E>
+where
+    F: Fn(TimedTransaction) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: From<sqlx::Error> + DatabaseCause,
+{
+    let body = &body;
+    rerunning(operation, move || async move {
+        body(begin(operation, pool).await?).await
+    })
+    .instrument(tracing::info_span!("db_query", operation))
+    .await
+}
+
+async fn rerunning<F, Fut, T, E>(operation: &'static str, mut attempt: F) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: DatabaseCause,
+{
+    let mut reruns = 0;
+    loop {
+        match attempt().await {
+            Err(e) if reruns < MAX_RETRIES.load(Ordering::Relaxed) => {
+                let Some(cause) = e.database_cause().filter(|cause| is_conflict(cause)) else {
+                    return Err(e);
+                };
+                reruns += 1;
+                tracing::warn!(operation, reruns, error = %cause, "Re-running conflicting transaction");
+                tokio::time::sleep(backoff(reruns)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Runs `query`, and runs it again, after a jittered backoff, while it
+/// fails with a transient error. Only use it for statements that are safe
+/// to repeat: reads, or the start of a transaction. A write whose
+/// connection dropped may already have been committed.
+pub async fn retrying<F, Fut, T>(operation: &'static str, mut query: F) -> Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let mut retries = 0;
+    loop {
+        match query()
+            .instrument(tracing::info_span!("db_query", operation))
+            .await
+        {
+            Err(e) if is_transient(&e) && retries < MAX_RETRIES.load(Ordering::Relaxed) => {
+                retries += 1;
+                tracing::warn!(operation, retries, error = %e, "Retrying transient database error");
+                tokio::time::sleep(backoff(retries)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+// Doubles on each retry, plus up to as much again at random so concurrent
+// retries of the same conflict do not collide a second time.
+fn backoff(retry: u32) -> Duration {
+    let base = RETRY_BASE_DELAY_MS.load(Ordering::Relaxed) << (retry - 1).min(10);
+    let jitter = RandomState::new().build_hasher().finish() % (base + 1);
+    Duration::from_millis(base + jitter)
+}
+
+// Like slow queries, acquires on a plain `TimedPool` pick up the operation
+// name from the `db_query` span.
+fn record_acquire(operation: Option<&'static str>, elapsed: Duration) {
+    let acquire_ms = elapsed.as_millis() as u64;
+
+    if elapsed >= acquire_warn_threshold() {
+        tracing::warn!(operation, acquire_ms, "Slow database connection acquire");
+    } else {
+        tracing::debug!(operation, acquire_ms, "Database connection acquired");
+    }
+}
+
+pub async fn warm_up(
+    pool: &PgPool,
+    connections: u32,
+    interval: Duration,
+) -> Result<(), sqlx::Error> {
+    // Holding every connection until the end forces the pool to open a new
+    // one on each step instead of handing back an idle one.
+    let mut warmed = Vec::with_capacity(connections as usize);
+    for opened in 1..=connections {
+        warmed.push(pool.acquire().await?);
+        tracing::info!(opened, target = connections, "Warming up database pool");
+        if opened < connections {
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures_util::StreamExt;
+    use sqlx::error::{DatabaseError, ErrorKind};
+    use std::borrow::Cow;
+
+    #[derive(Debug)]
+    struct FakeDatabaseError(&'static str);
+
+    impl std::fmt::Display for FakeDatabaseError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "SQLSTATE {}", self.0)
+        }
+    }
+
+    impl std::error::Error for FakeDatabaseError {}
+
+    impl DatabaseError for FakeDatabaseError {
+        fn message(&self) -> &str {
+            self.0
+        }
+
+        fn code(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed(self.0))
+        }
+
+        fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::Other
+        }
+    }
+
+    fn database_error(code: &'static str) -> sqlx::Error {
+        sqlx::Error::Database(Box::new(FakeDatabaseError(code)))
+    }
+
+    #[tokio::test]
+    async fn timed_row_returns_query_output() {
+        let output = timed_row(None, "SELECT 42", async { 42 }).await;
+        assert_eq!(output, 42);
+    }
+
+    #[tokio::test]
+    async fn timed_rows_passes_rows_and_errors_through() {
+        let rows = futures_util::stream::iter(vec![Ok(1), Ok(2), Err(sqlx::Error::RowNotFound)]);
+        let output: Vec<_> = timed_rows(None, "SELECT 1", Box::pin(rows)).collect().await;
+
+        assert!(matches!(
+            output[..],
+            [Ok(1), Ok(2), Err(sqlx::Error::RowNotFound)]
+        ));
+    }
+
+    #[tokio::test]
+    async fn connection_failure_is_retried() {
+        let mut attempts = 0;
+        let output = retrying("flaky", || {
+            attempts += 1;
+            let result = match attempts {
+                1 => Err(database_error("08006")),
+                _ => Ok(42),
+            };
+            async move { result }
+        })
+        .await;
+
+        assert_eq!(output.unwrap(), 42);
+        assert_eq!(attempts, 2);
+    }
+
+    #[tokio::test]
+    async fn serialization_failure_reruns_the_transaction() {
+        let mut attempts = 0;
+        let output = rerunning("conflicting", || {
+            attempts += 1;
+            let result = match attempts {
+                1 => Err(database_error("40001")),
+                2 => Err(database_error("40P01")),
+                _ => Ok(42),
+            };
+            async move { result }
+        })
+        .await;
+
+        assert_eq!(output.unwrap(), 42);
+        assert_eq!(attempts, 3);
+        assert!(!is_transient(&database_error("40001")));
+    }
+
+    #[tokio::test]
+    async fn permanent_errors_are_not_retried() {
+        let mut attempts = 0;
+        let output: Result<(), sqlx::Error> = retrying("unique", || {
+            attempts += 1;
+            async { Err(database_error("23505")) }
+        })
+        .await;
+
+        assert!(output.is_err());
+        assert_eq!(attempts, 1);
+    }
+}
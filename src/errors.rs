@@ -0,0 +1,245 @@
+use crate::{
+    db::DatabaseCause, locale::Locale, responses::error_envelope, validations::ValidationError,
+};
+use actix_web::{
+    error::JsonPayloadError,
+    http::{
+        header::{CONTENT_LANGUAGE, WWW_AUTHENTICATE},
+        StatusCode,
+    },
+    web::{JsonConfig, PathConfig, QueryConfig},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use std::fmt;
+
+/// Everything a handler can fail with. Each variant maps to one status and
+/// answers with the same `{"error": {"code", "message"}}` envelope the
+/// router uses for unknown routes, so clients parse a single shape.
+#[derive(Debug)]
+pub enum ApiError {
+    /// Well-formed input rejected by the validations, rendered in the
+    /// caller's language. JSON that doesn't parse is a `BadRequest`.
+    Validation(Locale, Vec<ValidationError>),
+    BadRequest(String),
+    InvalidUuid(String),
+    NotFound(&'static str),
+    /// Missing or wrong credentials for the `/admin` endpoints.
+    Unauthorized(&'static str),
+    /// The route exists but not for this method; the router adds `Allow`.
+    MethodNotAllowed,
+    Conflict(&'static str),
+    /// A body over the given number of bytes.
+    PayloadTooLarge(usize),
+    /// Over the client's rate limit; the middleware adds a `Retry-After`.
+    TooManyRequests,
+    /// Refused for now, by maintenance mode or load shedding; the
+    /// middleware adds a `Retry-After`.
+    Unavailable(&'static str),
+    Timeout(&'static str),
+    /// Never shown to the client; the details only go to the logs.
+    Database(sqlx::Error),
+    /// A third-party service we depend on (the seeding catalogue) failed.
+    Upstream(reqwest::Error),
+}
+
+impl ApiError {
+    pub fn invalid(req: &HttpRequest, errors: impl Into<Vec<ValidationError>>) -> Self {
+        Self::Validation(Locale::negotiate(req), errors.into())
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::BadRequest(message.into())
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Validation(..) => "invalid_input",
+            Self::BadRequest(_) => "bad_request",
+            Self::InvalidUuid(_) => "invalid_uuid",
+            Self::NotFound(_) => "not_found",
+            Self::Unauthorized(_) => "unauthorized",
+            Self::MethodNotAllowed => "method_not_allowed",
+            Self::Conflict(_) => "conflict",
+            Self::PayloadTooLarge(_) => "payload_too_large",
+            Self::TooManyRequests => "too_many_requests",
+            Self::Unavailable(_) => "service_unavailable",
+            Self::Timeout(_) => "gateway_timeout",
+            Self::Database(_) => "internal_error",
+            Self::Upstream(_) => "bad_gateway",
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Validation(locale, errors) => {
+                let messages: Vec<String> = errors
+                    .iter()
+                    .map(|error| error.localized(*locale))
+                    .collect();
+                f.write_str(&messages.join(" "))
+            }
+            Self::BadRequest(message) => f.write_str(message),
+            Self::InvalidUuid(segment) => write!(f, "'{}' is not a valid UUID.", segment),
+            Self::NotFound(message)
+            | Self::Unauthorized(message)
+            | Self::Conflict(message)
+            | Self::Unavailable(message)
+            | Self::Timeout(message) => f.write_str(message),
+            Self::MethodNotAllowed => f.write_str("This route does not accept that method."),
+            Self::PayloadTooLarge(limit) => {
+                write!(f, "The request body is larger than {} bytes.", limit)
+            }
+            Self::TooManyRequests => {
+                f.write_str("Too many requests; slow down and try again later.")
+            }
+            Self::Database(_) => f.write_str("Something went wrong on our side."),
+            Self::Upstream(_) => f.write_str("An upstream service could not be reached."),
+        }
+    }
+}
+
+impl DatabaseCause for ApiError {
+    fn database_cause(&self) -> Option<&sqlx::Error> {
+        match self {
+            Self::Database(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(error: sqlx::Error) -> Self {
+        Self::Database(error)
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(error: reqwest::Error) -> Self {
+        Self::Upstream(error)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Validation(..) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::BadRequest(_) | Self::InvalidUuid(_) => StatusCode::BAD_REQUEST,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            Self::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Upstream(_) => StatusCode::BAD_GATEWAY,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut body = error_envelope(self.code(), self.to_string());
+        let mut response = HttpResponse::build(self.status_code());
+
+        match self {
+            Self::Validation(locale, errors) => {
+                body["error"]["details"] =
+                    errors.iter().map(|error| error.to_json(*locale)).collect();
+                response.insert_header((CONTENT_LANGUAGE, locale.tag()));
+            }
+            Self::Unauthorized(_) => {
+                response.insert_header((WWW_AUTHENTICATE, "Bearer"));
+            }
+            Self::Database(e) => tracing::error!(error = %e, "Database error"),
+            Self::Upstream(e) => tracing::error!(error = %e, "Upstream request failed"),
+            _ => {}
+        }
+
+        response.json(body)
+    }
+}
+
+/// Body extraction for every JSON route, rejecting with the envelope
+/// instead of actix's plain-text errors. A route with its own size limit
+/// starts from this too.
+pub fn json_config() -> JsonConfig {
+    JsonConfig::default().error_handler(|error, _| {
+        match error {
+            JsonPayloadError::OverflowKnownLength { limit, .. }
+            | JsonPayloadError::Overflow { limit } => ApiError::PayloadTooLarge(limit),
+            JsonPayloadError::ContentType => {
+                ApiError::bad_request("Expected a body with Content-Type: application/json.")
+            }
+            error => ApiError::bad_request(error.to_string()),
+        }
+        .into()
+    })
+}
+
+pub fn query_config() -> QueryConfig {
+    QueryConfig::default().error_handler(|error, _| ApiError::bad_request(error.to_string()).into())
+}
+
+pub fn path_config() -> PathConfig {
+    PathConfig::default().error_handler(|error, _| ApiError::bad_request(error.to_string()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use serde_json::{json, Value};
+
+    async fn body_of(error: ApiError) -> Value {
+        let bytes = to_bytes(error.error_response().into_body()).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn validation_errors_carry_every_detail() {
+        let error = ApiError::Validation(
+            Locale::Pt,
+            vec![
+                ValidationError::new("invalid_author_name", ""),
+                ValidationError::new("invalid_author_nationality", ""),
+            ],
+        );
+        assert_eq!(error.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            error
+                .error_response()
+                .headers()
+                .get(CONTENT_LANGUAGE)
+                .unwrap(),
+            "pt"
+        );
+
+        let body = body_of(error).await;
+        assert_eq!(body["error"]["code"], "invalid_input");
+        assert_eq!(
+            body["error"]["details"][0],
+            json!({"code": "invalid_author_name", "message": "'' não é um nome de autor válido."})
+        );
+        assert_eq!(body["error"]["details"].as_array().unwrap().len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn database_errors_hide_the_cause() {
+        let error = ApiError::from(sqlx::Error::PoolTimedOut);
+        assert_eq!(error.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            body_of(error).await,
+            error_envelope("internal_error", "Something went wrong on our side.")
+        );
+    }
+
+    #[test]
+    fn not_found_maps_to_404() {
+        assert_eq!(
+            ApiError::NotFound("Author not found").status_code(),
+            StatusCode::NOT_FOUND
+        );
+    }
+}
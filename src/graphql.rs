@@ -0,0 +1,511 @@
+use crate::{
+    db::{retrying, ReadPool, WritePool},
+    errors::ApiError,
+    locale::Locale,
+    pagination::{Page, PageQuery},
+    routes::{
+        authors::{
+            author_has_books_error, delete_author_record, insert_author, update_author_record,
+            CreatedAuthor, NewAuthorData, UpdateAuthorData,
+        },
+        books::{
+            check_book, delete_book_error, delete_book_record, insert_book, title_conflict_error,
+            update_book_record, CreatedBook, NewBookData, UpdateBookData,
+        },
+        users::{email_conflict_error, insert_user, NewUserData},
+    },
+    validations::{
+        author::{AuthorUpdate, NewAuthor},
+        book::BookUpdate,
+        user::NewUser,
+        ValidationError,
+    },
+};
+use actix_web::web::Data;
+use async_graphql::{
+    dataloader::{DataLoader, Loader},
+    ComplexObject, Context, EmptySubscription, ErrorExtensions, Object, Result, Schema,
+    SimpleObject,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub type LibrarySchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+// Deep enough for the introspection query GraphiQL sends, the deepest
+// legitimate one, and cheap enough that one request can't ask for every
+// author many times over.
+const MAX_QUERY_DEPTH: usize = 15;
+const MAX_QUERY_COMPLEXITY: usize = 256;
+
+pub fn build_schema(read_pool: Data<ReadPool>, write_pool: Data<WritePool>) -> LibrarySchema {
+    let books_by_author = DataLoader::new(BooksByAuthor(read_pool.clone()), tokio::spawn);
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+        .data(read_pool)
+        .data(write_pool)
+        .data(books_by_author)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .limit_complexity(MAX_QUERY_COMPLEXITY)
+        .finish()
+}
+
+/// Resolvers fail through `ApiError`, like the REST handlers, so clients
+/// get the same message and `code` (as an extension) and never the SQL
+/// behind a database error.
+trait IntoGraphQL<T> {
+    fn into_graphql(self) -> Result<T>;
+}
+
+impl<T, E: Into<ApiError>> IntoGraphQL<T> for std::result::Result<T, E> {
+    fn into_graphql(self) -> Result<T> {
+        self.map_err(|error| graphql_error(&error.into()))
+    }
+}
+
+fn graphql_error(error: &ApiError) -> async_graphql::Error {
+    match error {
+        ApiError::Database(e) => tracing::error!(error = %e, "Database error"),
+        ApiError::Upstream(e) => tracing::error!(error = %e, "Upstream request failed"),
+        _ => {}
+    }
+    async_graphql::Error::new(error.to_string())
+        .extend_with(|_, extensions| extensions.set("code", error.code()))
+}
+
+fn invalid(errors: Vec<ValidationError>) -> ApiError {
+    ApiError::Validation(Locale::default(), errors)
+}
+
+/// Lists take `page` and `perPage` like the REST indexes, with the same
+/// defaults and cap, since query complexity counts fields and not rows.
+fn page(page: Option<i64>, per_page: Option<i64>) -> Result<Page> {
+    PageQuery { page, per_page }
+        .parse()
+        .map_err(ApiError::bad_request)
+        .into_graphql()
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct Author {
+    id: Uuid,
+    name: String,
+    nationality: String,
+    created_at: DateTime<Utc>,
+}
+
+#[ComplexObject]
+impl Author {
+    async fn books(
+        &self,
+        ctx: &Context<'_>,
+        page: Option<i64>,
+        per_page: Option<i64>,
+    ) -> Result<Vec<Book>> {
+        let page = self::page(page, per_page)?;
+        let books = ctx
+            .data::<DataLoader<BooksByAuthor>>()?
+            .load_one((self.id, page))
+            .await
+            .map_err(|error| graphql_error(&error))?;
+
+        Ok(books.unwrap_or_default())
+    }
+}
+
+impl From<CreatedAuthor> for Author {
+    fn from(author: CreatedAuthor) -> Self {
+        Self {
+            id: author.id,
+            name: author.name,
+            nationality: author.nationality,
+            created_at: author.created_at,
+        }
+    }
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct Book {
+    id: Uuid,
+    title: String,
+    author: String,
+    author_id: Uuid,
+    genre: String,
+    created_at: DateTime<Utc>,
+}
+
+impl From<CreatedBook> for Book {
+    fn from(book: CreatedBook) -> Self {
+        Self {
+            id: book.id,
+            title: book.title,
+            author: book.author,
+            author_id: book.author_id,
+            genre: book.genre,
+            created_at: book.created_at,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct User {
+    id: Uuid,
+    name: String,
+    email: String,
+    is_active: bool,
+    created_at: DateTime<Utc>,
+}
+
+/// Loads one page of books for every author in a response with one query,
+/// instead of one query per author.
+pub struct BooksByAuthor(Data<ReadPool>);
+
+impl Loader<(Uuid, Page)> for BooksByAuthor {
+    type Value = Vec<Book>;
+    type Error = Arc<ApiError>;
+
+    async fn load(
+        &self,
+        keys: &[(Uuid, Page)],
+    ) -> Result<HashMap<(Uuid, Page), Vec<Book>>, Self::Error> {
+        let author_ids: Vec<Uuid> = keys.iter().map(|(author_id, _)| *author_id).collect();
+        let pages: Vec<i64> = keys.iter().map(|(_, page)| page.page).collect();
+        let per_pages: Vec<i64> = keys.iter().map(|(_, page)| page.per_page).collect();
+        let rows = retrying("graphql.books_by_author", || {
+            sqlx::query!(
+                r#"
+                SELECT
+                    keys.page AS "page!",
+                    keys.per_page AS "per_page!",
+                    books.id,
+                    books.title,
+                    books.author AS "author",
+                    books.author_id,
+                    books.genre,
+                    books.created_at
+                FROM unnest($1::uuid[], $2::int8[], $3::int8[]) AS keys(author_id, page, per_page)
+                CROSS JOIN LATERAL (
+                    SELECT
+                        books.id,
+                        books.title,
+                        authors.name AS author,
+                        books.author_id,
+                        books.genre,
+                        books.created_at
+                    FROM books
+                    JOIN authors ON books.author_id = authors.id
+                    WHERE books.author_id = keys.author_id
+                    ORDER BY books.created_at ASC, books.id ASC
+                    LIMIT keys.per_page OFFSET (keys.page - 1) * keys.per_page
+                ) AS books
+                ORDER BY books.created_at ASC, books.id ASC
+                "#,
+                &author_ids,
+                &pages,
+                &per_pages
+            )
+            .fetch_all(&self.0 .0)
+        })
+        .await
+        .map_err(|e| Arc::new(ApiError::from(e)))?;
+
+        let mut books_by_author: HashMap<(Uuid, Page), Vec<Book>> = HashMap::new();
+        for row in rows {
+            let page = Page {
+                page: row.page,
+                per_page: row.per_page,
+            };
+            books_by_author
+                .entry((row.author_id, page))
+                .or_default()
+                .push(Book {
+                    id: row.id,
+                    title: row.title,
+                    author: row.author,
+                    author_id: row.author_id,
+                    genre: row.genre,
+                    created_at: row.created_at,
+                });
+        }
+        Ok(books_by_author)
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn authors(
+        &self,
+        ctx: &Context<'_>,
+        nationality: Option<String>,
+        page: Option<i64>,
+        per_page: Option<i64>,
+    ) -> Result<Vec<Author>> {
+        let db_pool = ctx.data::<Data<ReadPool>>()?;
+        let page = self::page(page, per_page)?;
+        let authors = retrying("graphql.authors", || {
+            sqlx::query_as!(
+                Author,
+                "SELECT id, name, nationality, created_at FROM authors
+                WHERE $1::text IS NULL OR nationality = $1
+                ORDER BY created_at ASC, id ASC
+                LIMIT $2 OFFSET $3",
+                nationality,
+                page.per_page,
+                page.offset()
+            )
+            .fetch_all(&db_pool.0)
+        })
+        .await
+        .into_graphql()?;
+
+        Ok(authors)
+    }
+
+    async fn author(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<Author>> {
+        let db_pool = ctx.data::<Data<ReadPool>>()?;
+        let author = retrying("graphql.author", || {
+            sqlx::query_as!(
+                Author,
+                "SELECT id, name, nationality, created_at FROM authors WHERE id = $1",
+                id
+            )
+            .fetch_optional(&db_pool.0)
+        })
+        .await
+        .into_graphql()?;
+
+        Ok(author)
+    }
+
+    async fn books(
+        &self,
+        ctx: &Context<'_>,
+        genre: Option<String>,
+        author_id: Option<Uuid>,
+        page: Option<i64>,
+        per_page: Option<i64>,
+    ) -> Result<Vec<Book>> {
+        fetch_books(ctx, genre, author_id, self::page(page, per_page)?).await
+    }
+
+    async fn book(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<Book>> {
+        let db_pool = ctx.data::<Data<ReadPool>>()?;
+        let book = retrying("graphql.book", || {
+            sqlx::query_as!(
+                Book,
+                r#"
+                SELECT
+                    books.id,
+                    books.title,
+                    authors.name AS "author",
+                    books.author_id,
+                    books.genre,
+                    books.created_at
+                FROM books
+                JOIN authors ON books.author_id = authors.id
+                WHERE books.id = $1
+                "#,
+                id
+            )
+            .fetch_optional(&db_pool.0)
+        })
+        .await
+        .into_graphql()?;
+
+        Ok(book)
+    }
+
+    async fn user(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<User>> {
+        let db_pool = ctx.data::<Data<ReadPool>>()?;
+        let user = retrying("graphql.user", || {
+            sqlx::query_as!(
+                User,
+                "SELECT id, name, email, is_active, created_at FROM users WHERE id = $1",
+                id
+            )
+            .fetch_optional(&db_pool.0)
+        })
+        .await
+        .into_graphql()?;
+
+        Ok(user)
+    }
+}
+
+async fn fetch_books(
+    ctx: &Context<'_>,
+    genre: Option<String>,
+    author_id: Option<Uuid>,
+    page: Page,
+) -> Result<Vec<Book>> {
+    let db_pool = ctx.data::<Data<ReadPool>>()?;
+    let books = retrying("graphql.books", || {
+        sqlx::query_as!(
+            Book,
+            r#"
+            SELECT
+                books.id,
+                books.title,
+                authors.name AS "author",
+                books.author_id,
+                books.genre,
+                books.created_at
+            FROM books
+            JOIN authors ON books.author_id = authors.id
+            WHERE ($1::text IS NULL OR books.genre = $1)
+                AND ($2::uuid IS NULL OR books.author_id = $2)
+            ORDER BY books.created_at ASC, books.id ASC
+            LIMIT $3 OFFSET $4
+            "#,
+            genre,
+            author_id,
+            page.per_page,
+            page.offset()
+        )
+        .fetch_all(&db_pool.0)
+    })
+    .await
+    .into_graphql()?;
+
+    Ok(books)
+}
+
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn create_author(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+        nationality: String,
+    ) -> Result<Uuid> {
+        let db_pool = ctx.data::<Data<WritePool>>()?;
+        let new_author = NewAuthor::try_from(NewAuthorData { name, nationality })
+            .map_err(invalid)
+            .into_graphql()?;
+
+        Ok(
+            insert_author(&new_author, db_pool.0.named("graphql.create_author"))
+                .await
+                .into_graphql()?
+                .id,
+        )
+    }
+
+    async fn update_author(
+        &self,
+        ctx: &Context<'_>,
+        id: Uuid,
+        name: Option<String>,
+        nationality: Option<String>,
+    ) -> Result<Author> {
+        let db_pool = ctx.data::<Data<WritePool>>()?;
+        let update = AuthorUpdate::try_from(UpdateAuthorData { name, nationality })
+            .map_err(invalid)
+            .into_graphql()?;
+        if update.is_empty() {
+            return Err(graphql_error(&ApiError::bad_request(
+                "Nothing to update; send a 'name' or a 'nationality'.",
+            )));
+        }
+
+        Ok(
+            update_author_record(id, &update, db_pool.0.named("graphql.update_author"))
+                .await
+                .into_graphql()?
+                .ok_or(ApiError::NotFound("Author not found"))
+                .into_graphql()?
+                .into(),
+        )
+    }
+
+    async fn delete_author(&self, ctx: &Context<'_>, id: Uuid) -> Result<bool> {
+        let db_pool = ctx.data::<Data<WritePool>>()?;
+
+        delete_author_record(id, db_pool.0.named("graphql.delete_author"))
+            .await
+            .map_err(author_has_books_error)
+            .into_graphql()
+    }
+
+    async fn create_book(
+        &self,
+        ctx: &Context<'_>,
+        title: String,
+        author: String,
+        genre: String,
+    ) -> Result<Uuid> {
+        let db_pool = ctx.data::<Data<WritePool>>()?;
+        let input = NewBookData {
+            title,
+            author,
+            author_id: None,
+            genre,
+            parent_book_id: None,
+        };
+        let (new_book, author_id) = check_book(input, &db_pool.0)
+            .await
+            .into_graphql()?
+            .map_err(invalid)
+            .into_graphql()?;
+
+        Ok(insert_book(&new_book, author_id, &db_pool.0)
+            .await
+            .map_err(title_conflict_error)
+            .into_graphql()?
+            .id)
+    }
+
+    async fn update_book(
+        &self,
+        ctx: &Context<'_>,
+        id: Uuid,
+        title: Option<String>,
+        genre: Option<String>,
+    ) -> Result<Book> {
+        let db_pool = ctx.data::<Data<WritePool>>()?;
+        let update = BookUpdate::try_from(UpdateBookData { title, genre })
+            .map_err(invalid)
+            .into_graphql()?;
+        if update.is_empty() {
+            return Err(graphql_error(&ApiError::bad_request(
+                "Nothing to update; send a 'title' or a 'genre'.",
+            )));
+        }
+
+        Ok(update_book_record(id, &update, &db_pool.0)
+            .await
+            .map_err(title_conflict_error)
+            .into_graphql()?
+            .ok_or(ApiError::NotFound("Book not found"))
+            .into_graphql()?
+            .into())
+    }
+
+    async fn delete_book(&self, ctx: &Context<'_>, id: Uuid) -> Result<bool> {
+        let db_pool = ctx.data::<Data<WritePool>>()?;
+
+        delete_book_record(id, &db_pool.0)
+            .await
+            .map_err(delete_book_error)
+            .into_graphql()
+    }
+
+    async fn create_user(&self, ctx: &Context<'_>, name: String, email: String) -> Result<Uuid> {
+        let db_pool = ctx.data::<Data<WritePool>>()?;
+        let new_user = NewUser::try_from(NewUserData { name, email })
+            .map_err(invalid)
+            .into_graphql()?;
+
+        Ok(insert_user(&new_user, &db_pool.0)
+            .await
+            .map_err(email_conflict_error)
+            .into_graphql()?
+            .id)
+    }
+}
@@ -0,0 +1,90 @@
+use crate::errors::ApiError;
+use actix_web::{dev::Payload, web, Error, FromRequest, HttpRequest};
+use futures_util::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::ops::Deref;
+
+/// Whether request bodies may carry fields the endpoint does not read.
+/// Lenient by default, like serde; `strict_json: true` turns a typo such
+/// as `nationalty` into a 400 instead of silently dropping the field.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StrictJson(pub bool);
+
+/// Deserializes `value`, rejecting unknown fields when `strict` is set.
+pub fn from_value<T: DeserializeOwned>(value: Value, strict: bool) -> Result<T, String> {
+    let mut unknown = Vec::new();
+    let parsed: T = serde_ignored::deserialize(value, |path| unknown.push(path.to_string()))
+        .map_err(|e| e.to_string())?;
+
+    match unknown.first() {
+        Some(field) if strict => Err(format!("Unknown field '{}'.", field)),
+        _ => Ok(parsed),
+    }
+}
+
+/// Drop-in for `web::Json` that honours `StrictJson`. Size limits and
+/// content type checks still come from the usual `JsonConfig`.
+pub struct Json<T>(pub T);
+
+impl<T> Json<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Json<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for Json<T> {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let strict = req
+            .app_data::<web::Data<StrictJson>>()
+            .is_some_and(|strict| strict.0);
+        let value = web::Json::<Value>::from_request(req, payload);
+
+        Box::pin(async move {
+            let value = value.await?.into_inner();
+            from_value(value, strict)
+                .map(Json)
+                .map_err(|e| ApiError::bad_request(e).into())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Deserialize, Debug)]
+    struct Author {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    #[test]
+    fn lenient_mode_ignores_unknown_fields() {
+        assert!(
+            from_value::<Author>(json!({"name": "Ana", "nationalty": "Brazilian"}), false).is_ok()
+        );
+    }
+
+    #[test]
+    fn strict_mode_names_the_unknown_field() {
+        assert_eq!(
+            from_value::<Author>(json!({"name": "Ana", "nationalty": "Brazilian"}), true)
+                .unwrap_err(),
+            "Unknown field 'nationalty'."
+        );
+    }
+}
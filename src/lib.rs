@@ -1,4 +1,19 @@
 pub mod configuration;
+pub mod csv;
+pub mod db;
+pub mod errors;
+pub mod graphql;
+pub mod json;
+pub mod locale;
+pub mod middleware;
+pub mod ndjson;
+pub mod pagination;
+pub mod responses;
 pub mod routes;
+pub mod self_test;
+pub mod shutdown;
+pub mod sorting;
 pub mod startup;
+pub mod telemetry;
+pub mod timezone;
 pub mod validations;
@@ -0,0 +1,190 @@
+use actix_web::{http::header::ACCEPT_LANGUAGE, HttpRequest};
+
+// Message templates keyed by error code; `{value}` is replaced by the
+// rejected input. The codes are part of the API and never change across
+// languages, only the text does.
+const EN: &[(&str, &str)] = &[
+    (
+        "invalid_author_name",
+        "'{value}' is not a valid author name.",
+    ),
+    (
+        "invalid_author_nationality",
+        "'{value}' is not a valid author nationality.",
+    ),
+    ("invalid_book_title", "'{value}' is not a valid book title."),
+    ("invalid_book_genre", "'{value}' is not a valid book genre."),
+    ("invalid_user_name", "'{value}' is not a valid user name."),
+    ("invalid_user_email", "'{value}' is not a valid user email."),
+    ("author_not_found", "Author '{value}' does not exist."),
+    ("parent_book_not_found", "Book '{value}' does not exist."),
+    (
+        "parent_book_is_edition",
+        "Book '{value}' is itself an edition and cannot have editions.",
+    ),
+    ("malformed_author", "Malformed author: {value}"),
+    (
+        "malformed_row",
+        "Row has {value} fields, unlike the header.",
+    ),
+];
+
+const PT: &[(&str, &str)] = &[
+    (
+        "invalid_author_name",
+        "'{value}' não é um nome de autor válido.",
+    ),
+    (
+        "invalid_author_nationality",
+        "'{value}' não é uma nacionalidade de autor válida.",
+    ),
+    (
+        "invalid_book_title",
+        "'{value}' não é um título de livro válido.",
+    ),
+    (
+        "invalid_book_genre",
+        "'{value}' não é um gênero de livro válido.",
+    ),
+    (
+        "invalid_user_name",
+        "'{value}' não é um nome de usuário válido.",
+    ),
+    (
+        "invalid_user_email",
+        "'{value}' não é um e-mail de usuário válido.",
+    ),
+    ("author_not_found", "O autor '{value}' não existe."),
+    ("parent_book_not_found", "O livro '{value}' não existe."),
+    (
+        "parent_book_is_edition",
+        "O livro '{value}' já é uma edição e não pode ter edições.",
+    ),
+    ("malformed_author", "Autor malformado: {value}"),
+    (
+        "malformed_row",
+        "A linha tem {value} campos, diferente do cabeçalho.",
+    ),
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Locale {
+    #[default]
+    En,
+    Pt,
+}
+
+impl Locale {
+    /// Picks the best supported language from `Accept-Language`, falling
+    /// back to English when none of the requested ones is available.
+    pub fn negotiate(req: &HttpRequest) -> Self {
+        req.headers()
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .map(Self::parse)
+            .unwrap_or_default()
+    }
+
+    pub fn parse(accept_language: &str) -> Self {
+        let mut ranges: Vec<(&str, f32)> = accept_language
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+                (quality > 0.0).then_some((tag, quality))
+            })
+            .collect();
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        ranges
+            .into_iter()
+            .find_map(|(tag, _)| Self::from_tag(tag))
+            .unwrap_or_default()
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.split('-').next().unwrap_or(tag);
+        match primary.to_ascii_lowercase().as_str() {
+            "en" => Some(Self::En),
+            "pt" => Some(Self::Pt),
+            _ => None,
+        }
+    }
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Pt => "pt",
+        }
+    }
+
+    fn catalog(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::En => EN,
+            Self::Pt => PT,
+        }
+    }
+
+    pub fn message(self, code: &str, value: &str) -> String {
+        let template = lookup(self.catalog(), code)
+            .or_else(|| lookup(EN, code))
+            .unwrap_or("{value}");
+        template.replace("{value}", value)
+    }
+}
+
+fn lookup(catalog: &[(&str, &'static str)], code: &str) -> Option<&'static str> {
+    catalog
+        .iter()
+        .find(|(key, _)| *key == code)
+        .map(|(_, template)| *template)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_or_unsupported_language_falls_back_to_english() {
+        assert_eq!(Locale::parse(""), Locale::En);
+        assert_eq!(Locale::parse("fr-FR, de;q=0.5"), Locale::En);
+    }
+
+    #[test]
+    fn region_subtags_are_ignored() {
+        assert_eq!(Locale::parse("pt-BR"), Locale::Pt);
+        assert_eq!(Locale::parse("EN-gb"), Locale::En);
+    }
+
+    #[test]
+    fn highest_quality_supported_language_wins() {
+        assert_eq!(Locale::parse("fr;q=0.9, en;q=0.5, pt-BR;q=0.8"), Locale::Pt);
+        assert_eq!(Locale::parse("pt;q=0, en"), Locale::En);
+    }
+
+    #[test]
+    fn every_locale_translates_every_code() {
+        for (code, _) in EN {
+            assert!(
+                lookup(PT, code).is_some(),
+                "missing pt message for {}",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn message_interpolates_value() {
+        assert_eq!(
+            Locale::Pt.message("invalid_author_name", ""),
+            "'' não é um nome de autor válido."
+        );
+        assert_eq!(
+            Locale::En.message("invalid_book_title", "Dune"),
+            "'Dune' is not a valid book title."
+        );
+    }
+}
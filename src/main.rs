@@ -1,17 +1,78 @@
 use std::net::TcpListener;
+use std::time::Duration;
 
-use midnight_library::{configuration::get_configuration, startup::run};
-use sqlx::PgPool;
+use midnight_library::{
+    configuration::get_configuration,
+    db,
+    self_test::self_test,
+    startup::run,
+    telemetry::{env_filter, get_subscriber, init_subscriber},
+};
+use sqlx::postgres::{PgPool, PgPoolOptions};
 
 #[tokio::main]
 async fn main() -> Result<(), std::io::Error> {
     let config = get_configuration().expect("Failed to read configuration.");
+    let env_filter = env_filter(&config.log_filter)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    init_subscriber(get_subscriber(
+        "midnight_library",
+        env_filter,
+        std::io::stdout,
+    ));
 
     let tcp_listener =
-        TcpListener::bind(config.server_address).expect("Failed to bind random port");
+        TcpListener::bind(&config.server_address).expect("Failed to bind random port");
 
-    let db_pool = PgPool::connect_lazy(config.database.database_url().as_str())
-        .expect("Failed to connect to Postgres.");
+    db::set_slow_query_threshold(Duration::from_millis(
+        config.database.slow_query_threshold_ms,
+    ));
+    db::set_acquire_warn_threshold(Duration::from_millis(
+        config.database.acquire_warn_threshold_ms,
+    ));
+    db::set_retry_policy(
+        config.database.max_retries,
+        Duration::from_millis(config.database.retry_base_delay_ms),
+    );
 
-    run(tcp_listener, db_pool)?.await
+    let db_pool = PgPoolOptions::new()
+        .min_connections(config.database.min_connections)
+        .connect_lazy_with(config.database.connect_options());
+    if let Err(e) = db::warm_up(
+        &db_pool,
+        config.database.min_connections,
+        Duration::from_millis(config.database.warmup_interval_ms),
+    )
+    .await
+    {
+        tracing::warn!(error = %e, "Failed to warm up the database pool");
+    }
+
+    if config.self_test {
+        if let Err(e) = self_test(&db::TimedPool::new(db_pool.clone())).await {
+            tracing::error!(error = %e, "Startup aborted");
+            return Err(std::io::Error::other(e));
+        }
+    }
+
+    let replica_pool = std::env::var("DATABASE_REPLICA_URL")
+        .ok()
+        .map(|replica_url| {
+            let options = config
+                .database
+                .replica_connect_options(&replica_url)
+                .expect("Invalid DATABASE_REPLICA_URL.");
+            PgPool::connect_lazy_with(options)
+        });
+
+    run(tcp_listener, db_pool.clone(), replica_pool.clone(), &config)?.await?;
+
+    // Connections are closed politely, so Postgres logs no resets on deploys.
+    db_pool.close().await;
+    if let Some(replica_pool) = replica_pool {
+        replica_pool.close().await;
+    }
+    tracing::info!("Database connections closed");
+
+    Ok(())
 }
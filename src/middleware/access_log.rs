@@ -0,0 +1,123 @@
+use actix_web::{
+    body::{BodySize, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    Error,
+};
+use chrono::{DateTime, FixedOffset, Local};
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLogFormat {
+    #[default]
+    Off,
+    Combined,
+}
+
+pub struct AccessLogEntry<'a> {
+    pub remote_addr: Option<&'a str>,
+    pub timestamp: DateTime<FixedOffset>,
+    pub request_line: &'a str,
+    pub status: u16,
+    pub bytes: Option<u64>,
+    pub referer: Option<&'a str>,
+    pub user_agent: Option<&'a str>,
+}
+
+pub fn combined_log_line(entry: &AccessLogEntry) -> String {
+    format!(
+        r#"{} - - [{}] "{}" {} {} "{}" "{}""#,
+        entry.remote_addr.unwrap_or("-"),
+        entry.timestamp.format("%d/%b/%Y:%H:%M:%S %z"),
+        entry.request_line,
+        entry.status,
+        entry
+            .bytes
+            .map_or_else(|| "-".to_string(), |bytes| bytes.to_string()),
+        entry.referer.unwrap_or("-"),
+        entry.user_agent.unwrap_or("-"),
+    )
+}
+
+pub async fn access_log(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let timestamp = Local::now().fixed_offset();
+    let remote_addr = req
+        .connection_info()
+        .realip_remote_addr()
+        .map(str::to_string);
+    let request_line = format!("{} {} {:?}", req.method(), req.uri(), req.version());
+    let header_value = |name| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let referer = header_value(header::REFERER);
+    let user_agent = header_value(header::USER_AGENT);
+
+    let res = next.call(req).await?;
+    let bytes = match res.response().body().size() {
+        BodySize::Sized(bytes) => Some(bytes),
+        BodySize::None | BodySize::Stream => None,
+    };
+
+    println!(
+        "{}",
+        combined_log_line(&AccessLogEntry {
+            remote_addr: remote_addr.as_deref(),
+            timestamp,
+            request_line: &request_line,
+            status: res.status().as_u16(),
+            bytes,
+            referer: referer.as_deref(),
+            user_agent: user_agent.as_deref(),
+        })
+    );
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_combined_log_line() {
+        let entry = AccessLogEntry {
+            remote_addr: Some("127.0.0.1"),
+            timestamp: DateTime::parse_from_rfc3339("2024-10-10T13:55:36-07:00").unwrap(),
+            request_line: "GET /books HTTP/1.1",
+            status: 200,
+            bytes: Some(2326),
+            referer: Some("http://example.com/start.html"),
+            user_agent: Some("curl/8.4.0"),
+        };
+
+        assert_eq!(
+            combined_log_line(&entry),
+            r#"127.0.0.1 - - [10/Oct/2024:13:55:36 -0700] "GET /books HTTP/1.1" 200 2326 "http://example.com/start.html" "curl/8.4.0""#
+        );
+    }
+
+    #[test]
+    fn missing_fields_are_dashes() {
+        let entry = AccessLogEntry {
+            remote_addr: None,
+            timestamp: DateTime::parse_from_rfc3339("2024-10-10T13:55:36+00:00").unwrap(),
+            request_line: "GET /books/export HTTP/1.1",
+            status: 200,
+            bytes: None,
+            referer: None,
+            user_agent: None,
+        };
+
+        assert_eq!(
+            combined_log_line(&entry),
+            r#"- - - [10/Oct/2024:13:55:36 +0000] "GET /books/export HTTP/1.1" 200 - "-" "-""#
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{errors::ApiError, middleware::routed_path};
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    web::Data,
+    Error, ResponseError,
+};
+
+pub struct AdminToken(pub Option<String>);
+
+pub async fn admin_auth(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let path = routed_path(&req);
+    let is_admin = path == "/admin" || path.starts_with("/admin/");
+    if is_admin {
+        let expected = req
+            .app_data::<Data<AdminToken>>()
+            .and_then(|token| token.0.clone());
+        let given = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        let refusal = match (expected, given) {
+            (None, _) => Some("The admin endpoints are disabled; set admin_token to use them."),
+            (Some(expected), Some(given)) if constant_time_eq(&expected, given) => None,
+            _ => Some("A valid admin bearer token is required."),
+        };
+        if let Some(message) = refusal {
+            return Ok(req
+                .into_response(ApiError::Unauthorized(message).error_response())
+                .map_into_right_body());
+        }
+    }
+
+    next.call(req)
+        .await
+        .map(ServiceResponse::map_into_left_body)
+}
+
+// Compares every byte, so the time taken does not reveal how much of a
+// guessed token was right.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{middleware::from_fn, test, web, App, HttpResponse};
+
+    async fn handler() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_web::test]
+    async fn admin_paths_need_the_configured_token() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(AdminToken(Some("secret".into()))))
+                .wrap(from_fn(admin_auth))
+                .route("/books", web::get().to(handler))
+                .route("/admin/backup", web::get().to(handler)),
+        )
+        .await;
+
+        let public =
+            test::call_service(&app, test::TestRequest::get().uri("/books").to_request()).await;
+        let anonymous = test::call_service(
+            &app,
+            test::TestRequest::get().uri("/admin/backup").to_request(),
+        )
+        .await;
+        let wrong = test::call_service(
+            &app,
+            test::TestRequest::get()
+                .uri("/admin/backup")
+                .insert_header((header::AUTHORIZATION, "Bearer secre"))
+                .to_request(),
+        )
+        .await;
+        let encoded = test::call_service(
+            &app,
+            test::TestRequest::get().uri("/%61dmin/backup").to_request(),
+        )
+        .await;
+        let authorized = test::call_service(
+            &app,
+            test::TestRequest::get()
+                .uri("/admin/backup")
+                .insert_header((header::AUTHORIZATION, "Bearer secret"))
+                .to_request(),
+        )
+        .await;
+
+        assert_eq!(public.status().as_u16(), 200);
+        assert_eq!(wrong.status().as_u16(), 401);
+        assert_eq!(encoded.status().as_u16(), 401);
+        assert_eq!(authorized.status().as_u16(), 200);
+        assert_eq!(anonymous.status().as_u16(), 401);
+        assert_eq!(
+            anonymous.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Bearer"
+        );
+        let body: serde_json::Value = test::read_body_json(anonymous).await;
+        assert_eq!(body["error"]["code"], "unauthorized");
+    }
+
+    #[actix_web::test]
+    async fn admin_paths_are_closed_without_a_token() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(AdminToken(None)))
+                .wrap(from_fn(admin_auth))
+                .route("/admin/backup", web::get().to(handler)),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::get()
+                .uri("/admin/backup")
+                .insert_header((header::AUTHORIZATION, "Bearer "))
+                .to_request(),
+        )
+        .await;
+
+        assert_eq!(response.status().as_u16(), 401);
+    }
+}
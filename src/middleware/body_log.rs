@@ -0,0 +1,232 @@
+use actix_web::{
+    body::{self, BodySize, BoxBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    error::ErrorInternalServerError,
+    http::header::{self, HeaderMap},
+    middleware::Next,
+    web::{BytesMut, Data},
+    Error, HttpMessage,
+};
+use futures_util::{stream, StreamExt};
+use serde_json::Value;
+
+const REDACTED: &str = "[REDACTED]";
+// Any JSON key containing one of these, case-insensitively, is redacted.
+const SENSITIVE_FIELDS: [&str; 5] = ["password", "token", "secret", "authorization", "api_key"];
+const SENSITIVE_HEADERS: [header::HeaderName; 4] = [
+    header::AUTHORIZATION,
+    header::PROXY_AUTHORIZATION,
+    header::COOKIE,
+    header::SET_COOKIE,
+];
+
+/// Debug-only logging of request and response bodies. Off unless
+/// `debug_body_log.enabled` is set in a configuration file; unlike other
+/// settings, `APP__` environment variables deliberately can't turn it on.
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct BodyLogConfig {
+    pub enabled: bool,
+    pub max_bytes: usize,
+}
+
+impl Default for BodyLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bytes: 2048,
+        }
+    }
+}
+
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let key = key.to_ascii_lowercase();
+                if SENSITIVE_FIELDS.iter().any(|field| key.contains(field)) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_value(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+/// Renders a body for the log: JSON gets its sensitive fields redacted,
+/// anything else is logged as (lossy) text, and both are cut at `max_bytes`.
+pub fn loggable_body(body: &[u8], max_bytes: usize) -> String {
+    let rendered = match serde_json::from_slice::<Value>(body) {
+        Ok(mut value) => {
+            redact_value(&mut value);
+            value.to_string()
+        }
+        Err(_) => String::from_utf8_lossy(body).into_owned(),
+    };
+
+    if rendered.len() <= max_bytes {
+        return rendered;
+    }
+    let end = char_boundary(&rendered, max_bytes);
+    format!(
+        "{}... ({} more bytes)",
+        &rendered[..end],
+        rendered.len() - end
+    )
+}
+
+/// Like `loggable_body`, for a request body of which only `prefix` was
+/// read. A cut JSON document can't be parsed to redact it, so only its
+/// size is logged.
+pub fn loggable_body_prefix(prefix: &[u8], max_bytes: usize) -> String {
+    let first = prefix.iter().find(|byte| !byte.is_ascii_whitespace());
+    if matches!(first, Some(b'{' | b'[')) {
+        return format!("<JSON body over {} bytes, not logged>", max_bytes);
+    }
+
+    let rendered = String::from_utf8_lossy(prefix);
+    let end = char_boundary(&rendered, max_bytes);
+    format!("{}... (more bytes not read)", &rendered[..end])
+}
+
+fn char_boundary(text: &str, max_bytes: usize) -> usize {
+    let mut end = max_bytes.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    end
+}
+
+pub fn loggable_headers(headers: &HeaderMap) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if SENSITIVE_HEADERS.contains(name) {
+                REDACTED
+            } else {
+                value.to_str().unwrap_or("<binary>")
+            };
+            format!("{}: {}", name, value)
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+pub async fn body_log(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let max_bytes = req.app_data::<Data<BodyLogConfig>>().map_or_else(
+        || BodyLogConfig::default().max_bytes,
+        |config| config.max_bytes,
+    );
+
+    // Only what the log can show is read up front; the rest of a large
+    // upload is left for the handler, whose own limit can turn it away.
+    let mut payload = req.take_payload();
+    let mut request_body = BytesMut::new();
+    let mut complete = false;
+    while request_body.len() <= max_bytes {
+        let Some(chunk) = payload.next().await else {
+            complete = true;
+            break;
+        };
+        request_body.extend_from_slice(&chunk?);
+    }
+    let request_body = request_body.freeze();
+    let logged_body = match complete {
+        true => loggable_body(&request_body, max_bytes),
+        false => loggable_body_prefix(&request_body, max_bytes),
+    };
+    tracing::debug!(
+        method = %req.method(),
+        path = %req.uri(),
+        headers = %loggable_headers(req.headers()),
+        body = %logged_body,
+        "Request body"
+    );
+    let rest = stream::once(async move { Ok(request_body) }).chain(payload);
+    req.set_payload(Payload::Stream {
+        payload: Box::pin(rest),
+    });
+
+    let res = next.call(req).await?.map_into_boxed_body();
+    // Streamed responses (exports, NDJSON, backups) would have to be
+    // buffered whole to be logged, so only their headers are.
+    if !matches!(res.response().body().size(), BodySize::Sized(_)) {
+        tracing::debug!(
+            status = res.status().as_u16(),
+            headers = %loggable_headers(res.headers()),
+            body = "<streamed>",
+            "Response body"
+        );
+        return Ok(res);
+    }
+
+    let (req, res) = res.into_parts();
+    let (res, response_body) = res.into_parts();
+    let response_body = body::to_bytes(response_body)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    tracing::debug!(
+        status = res.status().as_u16(),
+        headers = %loggable_headers(res.headers()),
+        body = %loggable_body(&response_body, max_bytes),
+        "Response body"
+    );
+
+    Ok(ServiceResponse::new(
+        req,
+        res.set_body(BoxBody::new(response_body)),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::HeaderValue;
+
+    #[test]
+    fn redacts_sensitive_fields_at_any_depth() {
+        let body = br#"{"name":"Ana","password":"hunter2","nested":[{"API_KEY":"k"}]}"#;
+        assert_eq!(
+            loggable_body(body, 1024),
+            r#"{"name":"Ana","nested":[{"API_KEY":"[REDACTED]"}],"password":"[REDACTED]"}"#
+        );
+    }
+
+    #[test]
+    fn truncates_on_char_boundary() {
+        assert_eq!(loggable_body("ééé".as_bytes(), 3), "é... (4 more bytes)");
+    }
+
+    #[test]
+    fn non_json_bodies_are_logged_as_text() {
+        assert_eq!(loggable_body(b"title,author", 1024), "title,author");
+    }
+
+    #[test]
+    fn cut_json_bodies_are_not_logged() {
+        assert_eq!(
+            loggable_body_prefix(br#" {"password":"hun"#, 16),
+            "<JSON body over 16 bytes, not logged>"
+        );
+        assert_eq!(
+            loggable_body_prefix(b"title,author\nDune", 12),
+            "title,author... (more bytes not read)"
+        );
+    }
+
+    #[test]
+    fn redacts_authorization_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer abc"),
+        );
+        assert_eq!(loggable_headers(&headers), "authorization: [REDACTED]");
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{errors::ApiError, middleware::routed_path};
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{self, HeaderValue},
+    middleware::Next,
+    web::Data,
+    Error, ResponseError,
+};
+use tokio::sync::Semaphore;
+
+const RETRY_AFTER_SECONDS: &str = "1";
+const EXEMPT_PATHS: [&str; 1] = ["/health_check"];
+
+/// Caps how many requests run their handlers at once, so a spike queues up
+/// as fast 503s instead of as timeouts waiting on the database pool.
+pub struct ConcurrencyLimit(Semaphore);
+
+impl ConcurrencyLimit {
+    pub fn new(max_in_flight: usize) -> Self {
+        Self(Semaphore::new(max_in_flight))
+    }
+}
+
+pub async fn concurrency_limit(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let limit = req.app_data::<Data<ConcurrencyLimit>>().cloned();
+    let is_exempt = EXEMPT_PATHS.contains(&routed_path(&req));
+
+    // The permit is held until the handler returns; streamed bodies keep
+    // flowing after that without counting against the limit.
+    let _permit = match &limit {
+        Some(limit) if !is_exempt => match limit.0.try_acquire() {
+            Ok(permit) => Some(permit),
+            Err(_) => {
+                let mut response = ApiError::Unavailable(
+                    "The server is handling too many requests; try again shortly.",
+                )
+                .error_response();
+                response.headers_mut().insert(
+                    header::RETRY_AFTER,
+                    HeaderValue::from_static(RETRY_AFTER_SECONDS),
+                );
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+        },
+        _ => None,
+    };
+
+    next.call(req)
+        .await
+        .map(ServiceResponse::map_into_left_body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{middleware::from_fn, test, web, App, HttpResponse};
+
+    async fn handler() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_web::test]
+    async fn saturated_limit_rejects_all_but_exempt_paths() {
+        let limit = Data::new(ConcurrencyLimit::new(1));
+        let app = test::init_service(
+            App::new()
+                .app_data(limit.clone())
+                .wrap(from_fn(concurrency_limit))
+                .route("/books", web::get().to(handler))
+                .route("/health_check", web::get().to(handler)),
+        )
+        .await;
+
+        let permit = limit.0.try_acquire().unwrap();
+        let rejected =
+            test::call_service(&app, test::TestRequest::get().uri("/books").to_request()).await;
+        let health = test::call_service(
+            &app,
+            test::TestRequest::get().uri("/health_check").to_request(),
+        )
+        .await;
+        let encoded = test::call_service(
+            &app,
+            test::TestRequest::get().uri("/h%65alth_check").to_request(),
+        )
+        .await;
+        drop(permit);
+        let accepted =
+            test::call_service(&app, test::TestRequest::get().uri("/books").to_request()).await;
+
+        assert_eq!(rejected.status().as_u16(), 503);
+        assert_eq!(
+            rejected.headers().get(header::RETRY_AFTER).unwrap(),
+            RETRY_AFTER_SECONDS
+        );
+        assert_eq!(health.status().as_u16(), 200);
+        assert_eq!(encoded.status().as_u16(), 200);
+        assert_eq!(accepted.status().as_u16(), 200);
+        let body: serde_json::Value = test::read_body_json(rejected).await;
+        assert_eq!(body["error"]["code"], "service_unavailable");
+    }
+}
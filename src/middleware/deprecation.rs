@@ -0,0 +1,44 @@
+use crate::routes::Endpoint;
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    middleware::Next,
+    web::Data,
+    Error,
+};
+
+pub async fn deprecation_headers(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let mut res = next.call(req).await?;
+
+    let deprecation = {
+        let request = res.request();
+        let method = request.method().as_str();
+        request
+            .match_pattern()
+            .zip(request.app_data::<Data<Vec<Endpoint>>>())
+            .and_then(|(pattern, endpoints)| {
+                endpoints
+                    .iter()
+                    .find(|endpoint| endpoint.path == pattern && endpoint.method == method)
+                    .and_then(|endpoint| endpoint.deprecation.clone())
+            })
+    };
+
+    if let Some(deprecation) = deprecation {
+        let headers = res.headers_mut();
+        headers.insert(
+            HeaderName::from_static("deprecation"),
+            HeaderValue::from_static("true"),
+        );
+        headers.insert(
+            HeaderName::from_static("sunset"),
+            HeaderValue::from_static(deprecation.sunset),
+        );
+    }
+
+    Ok(res)
+}
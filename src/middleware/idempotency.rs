@@ -0,0 +1,357 @@
+use crate::{
+    db::{TimedPool, WritePool},
+    errors::ApiError,
+    middleware::{routed_path, routed_pattern, RequestTimeout},
+    responses::error_envelope,
+    routes::Endpoint,
+};
+use actix_web::{
+    body::{self, BodySize, BoxBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    error::ErrorInternalServerError,
+    http::{
+        header::{self, HeaderMap, HeaderName, HeaderValue},
+        StatusCode,
+    },
+    middleware::Next,
+    web::{Bytes, BytesMut, Data},
+    Error, HttpMessage, HttpResponse, ResponseError,
+};
+use futures_util::StreamExt;
+use std::time::Duration;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+const MAX_KEY_LENGTH: usize = 255;
+/// How long a stored response is replayed; after that the key is free again.
+const KEY_TTL_SECS: f64 = 24.0 * 60.0 * 60.0;
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// The idempotent routes read their bodies with the default `JsonConfig`, so
+// nothing larger could have reached them anyway.
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+struct StoredRequest {
+    request_body: Vec<u8>,
+    response_status: Option<i16>,
+    response_headers: Option<Vec<String>>,
+    response_body: Option<Vec<u8>>,
+}
+
+/// Lets clients retry the routes marked `idempotent` safely: the first
+/// request carrying a given `Idempotency-Key` runs and its response is
+/// stored, and later ones with the same key and body get that response back
+/// instead of creating a duplicate. Requests without the header, and every
+/// other route, are untouched.
+pub async fn idempotency(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let Some(key) = req.headers().get(IDEMPOTENCY_KEY_HEADER).cloned() else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    let db_pool = req.app_data::<Data<WritePool>>().cloned();
+    let (Some(db_pool), true) = (db_pool, is_idempotent(&req)) else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    let key = match key.to_str() {
+        Ok(key) if !key.trim().is_empty() && key.len() <= MAX_KEY_LENGTH => key.to_string(),
+        _ => {
+            let error = ApiError::bad_request(format!(
+                "Idempotency-Key must be 1 to {} visible ASCII characters.",
+                MAX_KEY_LENGTH
+            ));
+            return Ok(req.into_response(error.error_response()));
+        }
+    };
+    // A request abandoned by a timeout never stores its response, so its
+    // claim on the key lapses once it could no longer be running.
+    let pending_ttl_secs = req
+        .app_data::<Data<RequestTimeout>>()
+        .map_or(30.0, |timeout| timeout.0.as_secs_f64());
+    let scope = format!("{} {}", req.method(), routed_path(&req));
+
+    let too_large = ApiError::PayloadTooLarge(MAX_BODY_BYTES);
+    let declared_length = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok()?.parse::<usize>().ok());
+    if declared_length.is_some_and(|length| length > MAX_BODY_BYTES) {
+        return Ok(req.into_response(too_large.error_response()));
+    }
+    let mut payload = req.take_payload();
+    let mut request_body = BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        if request_body.len() + chunk.len() > MAX_BODY_BYTES {
+            return Ok(req.into_response(too_large.error_response()));
+        }
+        request_body.extend_from_slice(&chunk);
+    }
+    let request_body = request_body.freeze();
+
+    let claimed = claim_key(&key, &scope, &request_body, pending_ttl_secs, &db_pool.0).await;
+    match claimed {
+        Ok(None) => {}
+        Ok(Some(stored)) => {
+            let response = replay(stored, &request_body);
+            return Ok(req.into_response(response));
+        }
+        Err(e) => return Ok(req.into_response(ApiError::from(e).error_response())),
+    }
+    req.set_payload(Payload::from(request_body));
+
+    let res = next.call(req).await?.map_into_boxed_body();
+    // Server errors are worth retrying for real, and streamed bodies can't
+    // be kept, so neither is stored.
+    if res.status().is_server_error() || !matches!(res.response().body().size(), BodySize::Sized(_))
+    {
+        release_key(&key, &scope, &db_pool.0).await;
+        return Ok(res);
+    }
+
+    let (req, res) = res.into_parts();
+    let (res, response_body) = res.into_parts();
+    let response_body = body::to_bytes(response_body)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    let status = res.status().as_u16() as i16;
+    let headers = stored_headers(res.headers());
+    if let Err(e) = store_response(&key, &scope, status, &headers, &response_body, &db_pool.0).await
+    {
+        // The request itself succeeded; a retry would just run it again.
+        tracing::error!(error = %e, "Failed to store idempotent response");
+        release_key(&key, &scope, &db_pool.0).await;
+    }
+
+    Ok(ServiceResponse::new(
+        req,
+        res.set_body(BoxBody::new(response_body)),
+    ))
+}
+
+fn is_idempotent(req: &ServiceRequest) -> bool {
+    let method = req.method().as_str();
+    routed_pattern(req)
+        .zip(req.app_data::<Data<Vec<Endpoint>>>())
+        .is_some_and(|(pattern, endpoints)| {
+            endpoints.iter().any(|endpoint| {
+                endpoint.path == pattern && endpoint.method == method && endpoint.idempotent
+            })
+        })
+}
+
+fn replay(stored: StoredRequest, request_body: &Bytes) -> HttpResponse {
+    if stored.request_body != request_body.as_ref() {
+        return HttpResponse::UnprocessableEntity().json(error_envelope(
+            "idempotency_key_reused",
+            "This Idempotency-Key was already used with a different request body.",
+        ));
+    }
+    let Some(status) = stored
+        .response_status
+        .and_then(|status| StatusCode::from_u16(status as u16).ok())
+    else {
+        return ApiError::Conflict(
+            "A request with this Idempotency-Key is still being processed; retry later.",
+        )
+        .error_response();
+    };
+
+    let mut response = HttpResponse::build(status);
+    for line in stored.response_headers.unwrap_or_default() {
+        if let Some((name, value)) = line.split_once(": ") {
+            response.append_header((name, value));
+        }
+    }
+    response.insert_header((
+        HeaderName::from_static(IDEMPOTENT_REPLAYED_HEADER),
+        HeaderValue::from_static("true"),
+    ));
+    response.body(stored.response_body.unwrap_or_default())
+}
+
+/// Headers are kept as `name: value` lines; values that aren't visible
+/// ASCII are dropped rather than mangled.
+fn stored_headers(headers: &HeaderMap) -> Vec<String> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| Some(format!("{}: {}", name, value.to_str().ok()?)))
+        .collect()
+}
+
+/// Returns `None` when this request now owns the key, or what is stored
+/// under it otherwise. A key whose response expired, or whose first request
+/// was abandoned, is taken over.
+async fn claim_key(
+    key: &str,
+    scope: &str,
+    request_body: &[u8],
+    pending_ttl_secs: f64,
+    db_pool: &TimedPool,
+) -> Result<Option<StoredRequest>, sqlx::Error> {
+    let claimed = sqlx::query!(
+        "INSERT INTO idempotency_keys (key, scope, request_body, created_at)
+        VALUES ($1, $2, $3, now())
+        ON CONFLICT (key, scope) DO UPDATE
+        SET request_body = EXCLUDED.request_body,
+            response_status = NULL,
+            response_headers = NULL,
+            response_body = NULL,
+            created_at = EXCLUDED.created_at
+        WHERE idempotency_keys.created_at < now() - make_interval(secs => $4)
+            OR (idempotency_keys.response_status IS NULL
+                AND idempotency_keys.created_at < now() - make_interval(secs => $5))",
+        key,
+        scope,
+        request_body,
+        KEY_TTL_SECS,
+        pending_ttl_secs
+    )
+    .execute(db_pool.named("idempotency.claim"))
+    .await?
+    .rows_affected()
+        == 1;
+    if claimed {
+        return Ok(None);
+    }
+
+    let stored = sqlx::query_as!(
+        StoredRequest,
+        "SELECT request_body, response_status, response_headers, response_body
+        FROM idempotency_keys
+        WHERE key = $1 AND scope = $2",
+        key,
+        scope
+    )
+    .fetch_optional(db_pool.named("idempotency.fetch"))
+    .await?;
+    // Released between the two statements: report it as still running,
+    // which a retry resolves either way.
+    Ok(Some(stored.unwrap_or(StoredRequest {
+        request_body: request_body.to_vec(),
+        response_status: None,
+        response_headers: None,
+        response_body: None,
+    })))
+}
+
+async fn store_response(
+    key: &str,
+    scope: &str,
+    status: i16,
+    headers: &[String],
+    body: &[u8],
+    db_pool: &TimedPool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE idempotency_keys
+        SET response_status = $3, response_headers = $4, response_body = $5
+        WHERE key = $1 AND scope = $2",
+        key,
+        scope,
+        status,
+        headers,
+        body
+    )
+    .execute(db_pool.named("idempotency.store"))
+    .await
+    .map(|_| ())
+}
+
+/// Deletes every key whose response is no longer replayed, returning how
+/// many went. Expired keys are already free to be claimed again; this only
+/// keeps the table from growing forever.
+pub async fn purge_expired_keys(db_pool: &TimedPool) -> Result<u64, sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM idempotency_keys WHERE created_at < now() - make_interval(secs => $1)",
+        KEY_TTL_SECS
+    )
+    .execute(db_pool.named("idempotency.purge"))
+    .await
+    .map(|result| result.rows_affected())
+}
+
+/// Runs `purge_expired_keys` every `PURGE_INTERVAL`, starting right away.
+pub async fn purge_expired_keys_periodically(db_pool: TimedPool) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        match purge_expired_keys(&db_pool).await {
+            Ok(purged) => tracing::info!(purged, "Purged expired idempotency keys"),
+            Err(e) => tracing::error!(error = %e, "Failed to purge expired idempotency keys"),
+        }
+    }
+}
+
+async fn release_key(key: &str, scope: &str, db_pool: &TimedPool) {
+    let released = sqlx::query!(
+        "DELETE FROM idempotency_keys WHERE key = $1 AND scope = $2",
+        key,
+        scope
+    )
+    .execute(db_pool.named("idempotency.release"))
+    .await;
+    if let Err(e) = released {
+        tracing::error!(error = %e, "Failed to release idempotency key");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header;
+
+    #[test]
+    fn stored_headers_round_trip_through_replay() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        headers.insert(header::LOCATION, HeaderValue::from_static("/books/1"));
+        let stored = StoredRequest {
+            request_body: b"{}".to_vec(),
+            response_status: Some(409),
+            response_headers: Some(stored_headers(&headers)),
+            response_body: Some(b"{}".to_vec()),
+        };
+
+        let response = replay(stored, &Bytes::from_static(b"{}"));
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(
+            response.headers().get(header::LOCATION).unwrap(),
+            "/books/1"
+        );
+        assert_eq!(
+            response.headers().get(IDEMPOTENT_REPLAYED_HEADER).unwrap(),
+            "true"
+        );
+    }
+
+    #[test]
+    fn a_different_body_is_not_replayed() {
+        let stored = StoredRequest {
+            request_body: b"{\"name\":\"A\"}".to_vec(),
+            response_status: Some(200),
+            response_headers: None,
+            response_body: None,
+        };
+
+        let response = replay(stored, &Bytes::from_static(b"{\"name\":\"B\"}"));
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[test]
+    fn a_pending_request_is_a_conflict() {
+        let stored = StoredRequest {
+            request_body: Vec::new(),
+            response_status: None,
+            response_headers: None,
+            response_body: None,
+        };
+
+        assert_eq!(replay(stored, &Bytes::new()).status(), StatusCode::CONFLICT);
+    }
+}
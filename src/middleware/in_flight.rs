@@ -0,0 +1,68 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web::Data,
+    Error,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Number of requests currently inside a handler, reported while draining
+/// on shutdown.
+#[derive(Default)]
+pub struct InFlight(AtomicUsize);
+
+impl InFlight {
+    pub fn count(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// Decrements on drop, so a request whose future is cancelled (a dropped
+// connection, a timeout) still stops counting.
+struct InFlightGuard(Data<InFlight>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0 .0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+pub async fn track_in_flight(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let _guard = req.app_data::<Data<InFlight>>().cloned().map(|in_flight| {
+        in_flight.0.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard(in_flight)
+    });
+
+    next.call(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{middleware::from_fn, test, web, App, HttpResponse};
+
+    async fn handler(in_flight: Data<InFlight>) -> HttpResponse {
+        HttpResponse::Ok().body(in_flight.count().to_string())
+    }
+
+    #[actix_web::test]
+    async fn counts_requests_while_they_run() {
+        let in_flight = Data::new(InFlight::default());
+        let app = test::init_service(
+            App::new()
+                .app_data(in_flight.clone())
+                .wrap(from_fn(track_in_flight))
+                .route("/", web::get().to(handler)),
+        )
+        .await;
+
+        let body = test::call_and_read_body(&app, test::TestRequest::get().to_request()).await;
+
+        assert_eq!(body, "1");
+        assert_eq!(in_flight.count(), 0);
+    }
+}
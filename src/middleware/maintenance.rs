@@ -0,0 +1,124 @@
+use crate::{errors::ApiError, middleware::routed_path};
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{header, Method},
+    middleware::Next,
+    web::Data,
+    Error, HttpResponse, ResponseError,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const RETRY_AFTER_SECONDS: &str = "120";
+// GraphQL reads are POSTs too, so `/graphql` decides for itself: the
+// handler refuses mutations with `maintenance_response`.
+const EXEMPT_PATHS: [&str; 2] = ["/admin/maintenance", "/graphql"];
+
+pub struct MaintenanceMode(AtomicBool);
+
+impl MaintenanceMode {
+    pub fn new(enabled: bool) -> Self {
+        Self(AtomicBool::new(enabled))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn set(&self, enabled: bool) {
+        self.0.store(enabled, Ordering::Relaxed);
+    }
+}
+
+pub async fn maintenance_mode(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let is_enabled = req
+        .app_data::<Data<MaintenanceMode>>()
+        .is_some_and(|mode| mode.is_enabled());
+    let is_read = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    let is_exempt = EXEMPT_PATHS.contains(&routed_path(&req));
+
+    if is_enabled && !is_read && !is_exempt {
+        return Ok(req
+            .into_response(maintenance_response())
+            .map_into_right_body());
+    }
+
+    next.call(req)
+        .await
+        .map(ServiceResponse::map_into_left_body)
+}
+
+pub fn maintenance_response() -> HttpResponse {
+    let mut response = ApiError::Unavailable(
+        "The service is in maintenance mode; writes are temporarily disabled.",
+    )
+    .error_response();
+    response.headers_mut().insert(
+        header::RETRY_AFTER,
+        header::HeaderValue::from_static(RETRY_AFTER_SECONDS),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{middleware::from_fn, test, web, App};
+
+    async fn handler() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_web::test]
+    async fn writes_are_rejected_while_reads_pass() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(MaintenanceMode::new(true)))
+                .wrap(from_fn(maintenance_mode))
+                .route("/books", web::get().to(handler))
+                .route("/books/create", web::post().to(handler)),
+        )
+        .await;
+
+        let read =
+            test::call_service(&app, test::TestRequest::get().uri("/books").to_request()).await;
+        let write = test::call_service(
+            &app,
+            test::TestRequest::post().uri("/books/create").to_request(),
+        )
+        .await;
+
+        assert_eq!(read.status().as_u16(), 200);
+        assert_eq!(write.status().as_u16(), 503);
+        assert_eq!(
+            write.headers().get(header::RETRY_AFTER).unwrap(),
+            RETRY_AFTER_SECONDS
+        );
+        let body: serde_json::Value = test::read_body_json(write).await;
+        assert_eq!(body["error"]["code"], "service_unavailable");
+    }
+
+    #[actix_web::test]
+    async fn exempt_paths_are_recognised_when_encoded() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(MaintenanceMode::new(true)))
+                .wrap(from_fn(maintenance_mode))
+                .route("/admin/maintenance", web::post().to(handler)),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/admin/m%61intenance")
+                .to_request(),
+        )
+        .await;
+
+        assert_eq!(response.status().as_u16(), 200);
+    }
+}
@@ -0,0 +1,38 @@
+pub mod access_log;
+pub mod admin_auth;
+pub mod body_log;
+pub mod concurrency_limit;
+pub mod deprecation;
+pub mod idempotency;
+pub mod in_flight;
+pub mod maintenance;
+pub mod rate_limit;
+pub mod request_id;
+pub mod request_timeout;
+
+pub use access_log::*;
+pub use admin_auth::*;
+pub use body_log::*;
+pub use concurrency_limit::*;
+pub use deprecation::*;
+pub use idempotency::*;
+pub use in_flight::*;
+pub use maintenance::*;
+pub use rate_limit::*;
+pub use request_id::*;
+pub use request_timeout::*;
+
+use actix_web::dev::ServiceRequest;
+
+/// The path the router matches routes against. Percent-encoded characters
+/// such as `%61` are already decoded here, unlike in `req.path()`, so a
+/// check on it can't be dodged by spelling the same route differently.
+pub fn routed_path(req: &ServiceRequest) -> &str {
+    req.match_info().as_str()
+}
+
+/// The route pattern `routed_path` resolves to, like `req.match_pattern()`
+/// but without being fooled by an encoded path.
+pub fn routed_pattern(req: &ServiceRequest) -> Option<String> {
+    req.request().resource_map().match_pattern(routed_path(req))
+}
@@ -0,0 +1,434 @@
+use crate::{errors::ApiError, middleware::routed_path};
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{self, HeaderValue},
+    middleware::Next,
+    web::Data,
+    Error, ResponseError,
+};
+use redis::aio::{ConnectionManager, ConnectionManagerConfig};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+const EXEMPT_PATHS: [&str; 1] = ["/health_check"];
+// Past this many tracked clients, buckets that have refilled completely are
+// dropped; they behave exactly like a client never seen before. The sweep
+// goes over every bucket, so it runs at most once per interval, however many
+// requests arrive meanwhile.
+const PRUNE_THRESHOLD: usize = 10_000;
+const PRUNE_INTERVAL: Duration = Duration::from_secs(10);
+const REDIS_TIMEOUT: Duration = Duration::from_millis(250);
+
+// Refills and takes one token atomically, using Redis' clock so that every
+// server sharing the bucket agrees on it. Returns whether the request is
+// allowed and the tokens left, as a string since Redis truncates numbers.
+const TOKEN_BUCKET_SCRIPT: &str = r"
+local rate = tonumber(ARGV[1])
+local burst = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) + tonumber(time[2]) / 1000000
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'updated_at')
+local tokens = tonumber(bucket[1]) or burst
+local updated_at = tonumber(bucket[2]) or now
+tokens = math.min(burst, tokens + math.max(0, now - updated_at) * rate)
+local allowed = 0
+if tokens >= 1 then
+  tokens = tokens - 1
+  allowed = 1
+end
+redis.call('HSET', KEYS[1], 'tokens', tokens, 'updated_at', now)
+redis.call('PEXPIRE', KEYS[1], math.ceil(burst / rate * 1000))
+return {allowed, tostring(tokens)}
+";
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitBackend {
+    /// Buckets live in this process; each server counts on its own.
+    #[default]
+    Memory,
+    /// Buckets live in Redis and are shared by every server using it.
+    Redis,
+}
+
+#[derive(serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// Sustained rate each client is allowed.
+    pub requests_per_second: f64,
+    /// Requests a client can make at once after being idle.
+    pub burst: u32,
+    /// Key clients on the first `X-Forwarded-For` address instead of the
+    /// peer address. Only safe behind a proxy that sets the header itself.
+    pub trust_forwarded_for: bool,
+    pub backend: RateLimitBackend,
+    pub redis_url: Option<String>,
+    /// Whether requests go through while Redis can't be reached. Off, they
+    /// get a 429 instead, since the limit can't be checked.
+    pub fail_open: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            requests_per_second: 10.0,
+            burst: 20,
+            trust_forwarded_for: false,
+            backend: RateLimitBackend::Memory,
+            redis_url: None,
+            fail_open: true,
+        }
+    }
+}
+
+// `redis_url` may carry a password, so it never reaches the logs.
+impl std::fmt::Debug for RateLimitConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimitConfig")
+            .field("enabled", &self.enabled)
+            .field("requests_per_second", &self.requests_per_second)
+            .field("burst", &self.burst)
+            .field("trust_forwarded_for", &self.trust_forwarded_for)
+            .field("backend", &self.backend)
+            .field("redis_url", &self.redis_url.as_ref().map(|_| "***"))
+            .field("fail_open", &self.fail_open)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+struct RedisStore {
+    client: redis::Client,
+    // Opened on first use, since `run` can't wait on it.
+    connection: OnceCell<ConnectionManager>,
+    script: redis::Script,
+}
+
+#[derive(Default)]
+struct MemoryBuckets {
+    buckets: HashMap<String, Bucket>,
+    pruned_at: Option<Instant>,
+}
+
+enum Store {
+    Memory(Mutex<MemoryBuckets>),
+    Redis(Box<RedisStore>),
+}
+
+/// Per-client token bucket: every client starts with `burst` tokens, each
+/// request takes one, and they refill at `requests_per_second`.
+pub struct RateLimiter {
+    requests_per_second: f64,
+    burst: f64,
+    trust_forwarded_for: bool,
+    fail_open: bool,
+    store: Store,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Result<Self, String> {
+        if config.requests_per_second <= 0.0 || config.burst == 0 {
+            return Err(String::from(
+                "rate_limit.requests_per_second and rate_limit.burst must be positive.",
+            ));
+        }
+        let store = match config.backend {
+            RateLimitBackend::Memory => Store::Memory(Mutex::default()),
+            RateLimitBackend::Redis => {
+                let url = config.redis_url.as_deref().ok_or_else(|| {
+                    String::from("rate_limit.redis_url is required by the redis backend.")
+                })?;
+                Store::Redis(Box::new(RedisStore {
+                    client: redis::Client::open(url).map_err(|e| e.to_string())?,
+                    connection: OnceCell::new(),
+                    script: redis::Script::new(TOKEN_BUCKET_SCRIPT),
+                }))
+            }
+        };
+
+        Ok(Self {
+            requests_per_second: config.requests_per_second,
+            burst: f64::from(config.burst),
+            trust_forwarded_for: config.trust_forwarded_for,
+            fail_open: config.fail_open,
+            store,
+        })
+    }
+
+    /// Identifies who a request counts against. Only the address for now;
+    /// once requests carry an API key it should take precedence here.
+    fn client_key(&self, req: &ServiceRequest) -> Option<String> {
+        let address = match self.trust_forwarded_for {
+            true => req
+                .connection_info()
+                .realip_remote_addr()
+                .map(str::to_string),
+            false => req.peer_addr().map(|addr| addr.ip().to_string()),
+        };
+        address.map(|address| format!("ip:{}", address))
+    }
+
+    /// `Ok` when the request may go ahead, or how long to wait before the
+    /// next one would.
+    async fn acquire(&self, client: &str) -> Result<(), Duration> {
+        let tokens_left = match &self.store {
+            Store::Memory(memory) => {
+                let mut memory = memory.lock().unwrap_or_else(|e| e.into_inner());
+                let now = Instant::now();
+                self.prune(&mut memory, now);
+                let bucket = memory.buckets.entry(client.to_string()).or_insert(Bucket {
+                    tokens: self.burst,
+                    updated_at: now,
+                });
+                let tokens = self.refilled(*bucket, now);
+                let allowed = tokens >= 1.0;
+                *bucket = Bucket {
+                    tokens: if allowed { tokens - 1.0 } else { tokens },
+                    updated_at: now,
+                };
+                allowed.then_some(()).ok_or(tokens)
+            }
+            Store::Redis(redis) => {
+                match self.acquire_in_redis(redis, client).await {
+                    Ok(result) => result,
+                    // Failing open by default: an unreachable Redis should
+                    // not take the whole API down with it.
+                    Err(e) => {
+                        tracing::warn!(
+                            error = %e,
+                            fail_open = self.fail_open,
+                            "Rate limiter could not reach Redis"
+                        );
+                        if self.fail_open {
+                            Ok(())
+                        } else {
+                            Err(0.0)
+                        }
+                    }
+                }
+            }
+        };
+
+        tokens_left.map_err(|tokens| {
+            Duration::from_secs_f64((1.0 - tokens).max(0.0) / self.requests_per_second)
+        })
+    }
+
+    fn prune(&self, memory: &mut MemoryBuckets, now: Instant) {
+        let due = memory.pruned_at.map_or(true, |pruned_at| {
+            now.saturating_duration_since(pruned_at) >= PRUNE_INTERVAL
+        });
+        if memory.buckets.len() > PRUNE_THRESHOLD && due {
+            memory
+                .buckets
+                .retain(|_, bucket| self.refilled(*bucket, now) < self.burst);
+            memory.pruned_at = Some(now);
+        }
+    }
+
+    fn refilled(&self, bucket: Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated_at);
+        (bucket.tokens + elapsed.as_secs_f64() * self.requests_per_second).min(self.burst)
+    }
+
+    async fn acquire_in_redis(
+        &self,
+        redis: &RedisStore,
+        client: &str,
+    ) -> redis::RedisResult<Result<(), f64>> {
+        let connection = redis
+            .connection
+            .get_or_try_init(|| {
+                ConnectionManager::new_with_config(redis.client.clone(), redis_config())
+            })
+            .await?;
+        let (allowed, tokens): (i64, String) = redis
+            .script
+            .key(format!("rate_limit:{}", client))
+            .arg(self.requests_per_second)
+            .arg(self.burst)
+            .invoke_async(&mut connection.clone())
+            .await?;
+        let tokens = tokens.parse().unwrap_or(0.0);
+        Ok(if allowed == 1 { Ok(()) } else { Err(tokens) })
+    }
+}
+
+// Every request waits on Redis, so a down Redis has to fail fast rather
+// than after the client's default reconnect backoff of several seconds.
+fn redis_config() -> ConnectionManagerConfig {
+    ConnectionManagerConfig::new()
+        .set_number_of_retries(0)
+        .set_connection_timeout(REDIS_TIMEOUT)
+        .set_response_timeout(REDIS_TIMEOUT)
+}
+
+pub async fn rate_limit(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let limiter = req.app_data::<Data<RateLimiter>>().cloned();
+    let is_exempt = EXEMPT_PATHS.contains(&routed_path(&req));
+
+    if let (Some(limiter), false) = (limiter, is_exempt) {
+        if let Some(client) = limiter.client_key(&req) {
+            if let Err(wait) = limiter.acquire(&client).await {
+                let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+                let mut response = ApiError::TooManyRequests.error_response();
+                response
+                    .headers_mut()
+                    .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+        }
+    }
+
+    next.call(req)
+        .await
+        .map(ServiceResponse::map_into_left_body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{middleware::from_fn, test, web, App, HttpResponse};
+
+    async fn handler() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    fn limiter(requests_per_second: f64, burst: u32) -> RateLimiter {
+        RateLimiter::new(&RateLimitConfig {
+            enabled: true,
+            requests_per_second,
+            burst,
+            ..RateLimitConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn exhausted_bucket_answers_429_except_on_exempt_paths() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(limiter(0.5, 2)))
+                .wrap(from_fn(rate_limit))
+                .route("/books", web::get().to(handler))
+                .route("/health_check", web::get().to(handler)),
+        )
+        .await;
+        let request = |uri| {
+            test::TestRequest::get()
+                .uri(uri)
+                .peer_addr("10.0.0.1:4000".parse().unwrap())
+                .to_request()
+        };
+
+        let first = test::call_service(&app, request("/books")).await;
+        let second = test::call_service(&app, request("/books")).await;
+        let rejected = test::call_service(&app, request("/books")).await;
+        let health = test::call_service(&app, request("/health_check")).await;
+        let encoded = test::call_service(&app, request("/h%65alth_check")).await;
+
+        assert_eq!(first.status().as_u16(), 200);
+        assert_eq!(second.status().as_u16(), 200);
+        assert_eq!(rejected.status().as_u16(), 429);
+        assert_eq!(rejected.headers().get(header::RETRY_AFTER).unwrap(), "2");
+        assert_eq!(health.status().as_u16(), 200);
+        assert_eq!(encoded.status().as_u16(), 200);
+        let body: serde_json::Value = test::read_body_json(rejected).await;
+        assert_eq!(body["error"]["code"], "too_many_requests");
+    }
+
+    #[actix_web::test]
+    async fn clients_have_separate_buckets() {
+        let limiter = limiter(1.0, 1);
+
+        assert!(limiter.acquire("ip:10.0.0.1").await.is_ok());
+        assert!(limiter.acquire("ip:10.0.0.1").await.is_err());
+        assert!(limiter.acquire("ip:10.0.0.2").await.is_ok());
+    }
+
+    #[actix_web::test]
+    async fn tokens_refill_up_to_the_burst() {
+        let limiter = limiter(2.0, 5);
+        let start = Instant::now();
+        let bucket = Bucket {
+            tokens: 0.0,
+            updated_at: start,
+        };
+
+        assert_eq!(
+            limiter.refilled(bucket, start + Duration::from_secs(1)),
+            2.0
+        );
+        assert_eq!(
+            limiter.refilled(bucket, start + Duration::from_secs(60)),
+            5.0
+        );
+    }
+
+    #[actix_web::test]
+    async fn pruning_runs_at_most_once_per_interval() {
+        let limiter = limiter(1.0, 1);
+        let start = Instant::now();
+        let mut memory = MemoryBuckets::default();
+        let full = Bucket {
+            tokens: 1.0,
+            updated_at: start,
+        };
+        let empty = Bucket {
+            tokens: 0.0,
+            updated_at: start,
+        };
+        for client in 0..=PRUNE_THRESHOLD {
+            memory.buckets.insert(format!("ip:{}", client), empty);
+        }
+        memory.buckets.insert(String::from("ip:idle"), full);
+
+        limiter.prune(&mut memory, start);
+        assert!(!memory.buckets.contains_key("ip:idle"));
+
+        memory.buckets.insert(String::from("ip:idle"), full);
+        limiter.prune(&mut memory, start + PRUNE_INTERVAL / 2);
+        assert!(memory.buckets.contains_key("ip:idle"));
+
+        limiter.prune(&mut memory, start + PRUNE_INTERVAL);
+        assert!(!memory.buckets.contains_key("ip:idle"));
+    }
+
+    #[actix_web::test]
+    async fn unreachable_redis_fails_open_unless_configured_not_to() {
+        let redis_limiter = |fail_open| {
+            RateLimiter::new(&RateLimitConfig {
+                backend: RateLimitBackend::Redis,
+                redis_url: Some(String::from("redis://127.0.0.1:1")),
+                fail_open,
+                ..RateLimitConfig::default()
+            })
+            .unwrap()
+        };
+
+        assert!(redis_limiter(true).acquire("ip:10.0.0.1").await.is_ok());
+        assert!(redis_limiter(false).acquire("ip:10.0.0.1").await.is_err());
+    }
+
+    #[actix_web::test]
+    async fn redis_backend_needs_a_url() {
+        let config = RateLimitConfig {
+            backend: RateLimitBackend::Redis,
+            ..RateLimitConfig::default()
+        };
+        assert!(RateLimiter::new(&config).is_err());
+    }
+}
@@ -0,0 +1,30 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    middleware::Next,
+    Error, HttpMessage,
+};
+use tracing_actix_web::RequestId;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Echoes the id `TracingLogger` gave the request, so a client reporting a
+/// failure can hand over the exact key to search the logs for. Must be
+/// wrapped inside `TracingLogger`, which is what stores the id.
+pub async fn request_id_header(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let request_id = req.extensions().get::<RequestId>().copied();
+    let mut res = next.call(req).await?;
+
+    if let Some(request_id) = request_id {
+        if let Ok(value) = HeaderValue::from_str(&request_id.to_string()) {
+            res.headers_mut()
+                .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+        }
+    }
+
+    Ok(res)
+}
@@ -0,0 +1,80 @@
+use crate::{errors::ApiError, middleware::routed_path};
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web::Data,
+    Error,
+};
+use std::time::Duration;
+
+const EXEMPT_PATHS: [&str; 1] = ["/books/export"];
+
+#[derive(Clone, Copy)]
+pub struct RequestTimeout(pub Duration);
+
+pub async fn request_timeout(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let timeout = req
+        .app_data::<Data<RequestTimeout>>()
+        .map(|timeout| timeout.0);
+    let is_exempt = EXEMPT_PATHS.contains(&routed_path(&req));
+
+    match timeout {
+        Some(duration) if !is_exempt => tokio::time::timeout(duration, next.call(req))
+            .await
+            .map_err(|_| ApiError::Timeout("Request took too long to complete."))?,
+        _ => next.call(req).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{middleware::from_fn, test, web, App, HttpResponse};
+
+    async fn slow_handler() -> HttpResponse {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_web::test]
+    async fn slow_request_times_out() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RequestTimeout(Duration::from_millis(20))))
+                .wrap(from_fn(request_timeout))
+                .route("/slow", web::get().to(slow_handler)),
+        )
+        .await;
+
+        let request = test::TestRequest::get().uri("/slow").to_request();
+        let error = test::try_call_service(&app, request)
+            .await
+            .err()
+            .expect("Request should have timed out.");
+
+        assert_eq!(error.as_response_error().status_code().as_u16(), 504);
+    }
+
+    #[actix_web::test]
+    async fn export_paths_are_exempt() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RequestTimeout(Duration::from_millis(20))))
+                .wrap(from_fn(request_timeout))
+                .route("/books/export", web::get().to(slow_handler)),
+        )
+        .await;
+
+        let request = test::TestRequest::get().uri("/books/export").to_request();
+        let response = test::call_service(&app, request).await;
+        let request = test::TestRequest::get().uri("/books/%65xport").to_request();
+        let encoded = test::call_service(&app, request).await;
+
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(encoded.status().as_u16(), 200);
+    }
+}
@@ -0,0 +1,50 @@
+use actix_web::{http::header, web::Bytes, HttpRequest};
+use serde_json::Value;
+
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+pub fn accepts_ndjson(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .split(',')
+                .any(|media_type| media_type.trim().starts_with(NDJSON_CONTENT_TYPE))
+        })
+        .unwrap_or(false)
+}
+
+pub fn ndjson_line(value: &Value) -> Bytes {
+    let mut line = value.to_string();
+    line.push('\n');
+    Bytes::from(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use serde_json::json;
+
+    #[test]
+    fn line_is_newline_terminated_json() {
+        assert_eq!(
+            ndjson_line(&json!({"title": "Dune"})),
+            Bytes::from("{\"title\":\"Dune\"}\n")
+        );
+    }
+
+    #[test]
+    fn detects_ndjson_accept_header() {
+        let ndjson = TestRequest::default()
+            .insert_header((header::ACCEPT, "application/json, application/x-ndjson"))
+            .to_http_request();
+        let json = TestRequest::default()
+            .insert_header((header::ACCEPT, "application/json"))
+            .to_http_request();
+
+        assert!(accepts_ndjson(&ndjson));
+        assert!(!accepts_ndjson(&json));
+    }
+}
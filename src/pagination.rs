@@ -0,0 +1,144 @@
+use actix_web::{http::header, HttpRequest, HttpResponseBuilder};
+use serde::Deserialize;
+
+pub const DEFAULT_PER_PAGE: i64 = 50;
+pub const MAX_PER_PAGE: i64 = 200;
+pub const TOTAL_COUNT_HEADER: &str = "X-Total-Count";
+
+#[derive(Deserialize)]
+pub struct PageQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Page {
+    pub page: i64,
+    pub per_page: i64,
+}
+
+impl PageQuery {
+    /// Pages count from 1. `per_page` above the maximum is clamped rather
+    /// than rejected.
+    pub fn parse(&self) -> Result<Page, String> {
+        let page = self.page.unwrap_or(1);
+        if page < 1 {
+            return Err(format!("'{}' is not a valid page.", page));
+        }
+        let per_page = self.per_page.unwrap_or(DEFAULT_PER_PAGE);
+        if per_page < 1 {
+            return Err(format!("'{}' is not a valid per_page.", per_page));
+        }
+
+        Ok(Page {
+            page,
+            per_page: per_page.min(MAX_PER_PAGE),
+        })
+    }
+}
+
+impl Page {
+    pub fn offset(&self) -> i64 {
+        (self.page - 1).saturating_mul(self.per_page)
+    }
+
+    fn has_next(&self, total: i64) -> bool {
+        self.page.saturating_mul(self.per_page) < total
+    }
+
+    /// `Link` header value with `next`/`prev` URLs that keep every other
+    /// query parameter of `req`, or `None` on a lone page.
+    fn links(&self, req: &HttpRequest, total: i64) -> Option<String> {
+        let kept: Vec<&str> = req
+            .query_string()
+            .split('&')
+            .filter(|pair| !pair.is_empty() && !pair.starts_with("page="))
+            .collect();
+        let link = |page: i64, rel: &str| {
+            let mut query = kept.clone();
+            let page = format!("page={}", page);
+            query.push(&page);
+            format!("<{}?{}>; rel=\"{}\"", req.path(), query.join("&"), rel)
+        };
+
+        let mut links = Vec::new();
+        if self.has_next(total) {
+            links.push(link(self.page + 1, "next"));
+        }
+        if self.page > 1 {
+            links.push(link(self.page - 1, "prev"));
+        }
+        (!links.is_empty()).then(|| links.join(", "))
+    }
+}
+
+pub trait Paginated {
+    /// Adds `X-Total-Count` and, when there are other pages, `Link`.
+    fn page_headers(&mut self, req: &HttpRequest, page: Page, total: i64) -> &mut Self;
+}
+
+impl Paginated for HttpResponseBuilder {
+    fn page_headers(&mut self, req: &HttpRequest, page: Page, total: i64) -> &mut Self {
+        self.insert_header((TOTAL_COUNT_HEADER, total.to_string()));
+        if let Some(links) = page.links(req, total) {
+            self.insert_header((header::LINK, links));
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn defaults_and_clamping() {
+        let query = PageQuery {
+            page: None,
+            per_page: Some(MAX_PER_PAGE + 1),
+        };
+        assert_eq!(
+            query.parse(),
+            Ok(Page {
+                page: 1,
+                per_page: MAX_PER_PAGE
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_pages_below_one() {
+        let query = PageQuery {
+            page: Some(0),
+            per_page: None,
+        };
+        assert!(query.parse().is_err());
+    }
+
+    #[test]
+    fn links_keep_other_params() {
+        let req = TestRequest::get()
+            .uri("/books?sort=title&page=2&per_page=10")
+            .to_http_request();
+        let page = Page {
+            page: 2,
+            per_page: 10,
+        };
+        assert_eq!(
+            page.links(&req, 25).unwrap(),
+            "</books?sort=title&per_page=10&page=3>; rel=\"next\", \
+             </books?sort=title&per_page=10&page=1>; rel=\"prev\""
+        );
+    }
+
+    #[test]
+    fn single_page_has_no_links() {
+        let req = TestRequest::get().uri("/books").to_http_request();
+        let page = Page {
+            page: 1,
+            per_page: 50,
+        };
+        assert!(page.links(&req, 3).is_none());
+    }
+}
@@ -0,0 +1,18 @@
+use serde_json::{json, Value};
+
+pub fn error_envelope(code: &str, message: impl Into<String>) -> Value {
+    json!({"error": {"code": code, "message": message.into()}})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_envelope_shape() {
+        assert_eq!(
+            error_envelope("not_found", "route not found"),
+            json!({"error": {"code": "not_found", "message": "route not found"}})
+        );
+    }
+}
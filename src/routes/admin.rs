@@ -0,0 +1,611 @@
+use crate::{
+    configuration::BulkLimits,
+    db::{begin, in_transaction, is_conflict, TimedPool, TimedTransaction, WritePool},
+    errors::{json_config, ApiError},
+    json::Json,
+    middleware::MaintenanceMode,
+    routes::{api_route, contains_pattern, ApiRoute},
+    validations::book::ValidatedBookGenre,
+};
+use actix_web::{
+    web::{self, Bytes, Data, Query},
+    HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use futures_util::{stream::BoxStream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+const MAX_RESTORE_BYTES: usize = 64 * 1024 * 1024;
+
+pub fn routes() -> Vec<ApiRoute> {
+    vec![
+        api_route(
+            "POST",
+            "/admin/maintenance",
+            "Toggle maintenance mode, which rejects writes",
+            set_maintenance_mode,
+        ),
+        api_route(
+            "POST",
+            "/admin/books/reassign_genre",
+            "Move every book from one genre to another",
+            reassign_genre,
+        ),
+        api_route(
+            "POST",
+            "/admin/books/tag",
+            "Set the genre of every book matching a filter",
+            tag_books,
+        ),
+        api_route(
+            "PUT",
+            "/admin/books/featured",
+            "Replace the featured books with an ordered list of ids",
+            set_featured_books,
+        ),
+        api_route(
+            "GET",
+            "/admin/backup",
+            "Download every author, book and user as one JSON document",
+            backup,
+        ),
+        restore_route(),
+    ]
+}
+
+fn restore_route() -> ApiRoute {
+    let mut route = api_route(
+        "POST",
+        "/admin/restore",
+        "Load a JSON document produced by /admin/backup",
+        restore,
+    );
+    route.resource = route
+        .resource
+        .app_data(json_config().limit(MAX_RESTORE_BYTES));
+    route
+}
+
+#[derive(Deserialize)]
+pub struct MaintenanceModeData {
+    enabled: bool,
+}
+
+pub async fn set_maintenance_mode(
+    input: Json<MaintenanceModeData>,
+    maintenance: Data<MaintenanceMode>,
+) -> HttpResponse {
+    maintenance.set(input.enabled);
+
+    HttpResponse::Ok().json(json!({"maintenance_mode": maintenance.is_enabled()}))
+}
+
+#[derive(Deserialize)]
+pub struct DryRunParams {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+async fn finish(transaction: TimedTransaction, dry_run: bool) -> Result<(), sqlx::Error> {
+    if dry_run {
+        transaction.rollback().await
+    } else {
+        transaction.commit().await
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ReassignGenreData {
+    from: String,
+    to: String,
+}
+
+pub async fn reassign_genre(
+    req: HttpRequest,
+    input: Json<ReassignGenreData>,
+    params: Query<DryRunParams>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    let input = input.into_inner();
+    let (from, to) = match (
+        ValidatedBookGenre::new(input.from),
+        ValidatedBookGenre::new(input.to),
+    ) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(e), _) | (_, Err(e)) => return Err(ApiError::invalid(&req, [e])),
+    };
+
+    let (from, to, dry_run) = (&from, &to, params.dry_run);
+    let books_updated =
+        in_transaction("reassign_genre", &db_pool.0, |mut transaction| async move {
+            let result = sqlx::query!(
+                "UPDATE books SET genre = $2 WHERE genre = $1",
+                from.as_ref(),
+                to.as_ref()
+            )
+            .execute(&mut transaction)
+            .await?;
+
+            finish(transaction, dry_run).await?;
+            Ok::<_, ApiError>(result.rows_affected())
+        })
+        .await?;
+
+    let message = match params.dry_run {
+        true => "Dry run: no changes were committed.",
+        false => "Genre reassigned successfully!",
+    };
+    Ok(HttpResponse::Ok().json(json!({
+        "message": message,
+        "dry_run": params.dry_run,
+        "books_updated": books_updated
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct TagBooksData {
+    title_contains: Option<String>,
+    author_id: Option<Uuid>,
+    genre: String,
+}
+
+pub async fn tag_books(
+    req: HttpRequest,
+    input: Json<TagBooksData>,
+    params: Query<DryRunParams>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    let input = input.into_inner();
+    let genre = ValidatedBookGenre::new(input.genre).map_err(|e| ApiError::invalid(&req, [e]))?;
+    let title_pattern = input
+        .title_contains
+        .as_deref()
+        .map(str::trim)
+        .filter(|term| !term.is_empty())
+        .map(contains_pattern);
+    // Without a filter this would silently retag the whole catalog.
+    if title_pattern.is_none() && input.author_id.is_none() {
+        return Err(ApiError::bad_request(
+            "At least one of 'title_contains' or 'author_id' is required.",
+        ));
+    }
+
+    let (genre, title_pattern, dry_run) = (&genre, &title_pattern, params.dry_run);
+    let books_updated = in_transaction("tag_books", &db_pool.0, |mut transaction| async move {
+        let result = sqlx::query!(
+            "UPDATE books SET genre = $1
+            WHERE ($2::text IS NULL OR title ILIKE $2)
+                AND ($3::uuid IS NULL OR author_id = $3)",
+            genre.as_ref(),
+            title_pattern.as_deref(),
+            input.author_id
+        )
+        .execute(&mut transaction)
+        .await?;
+
+        finish(transaction, dry_run).await?;
+        Ok::<_, ApiError>(result.rows_affected())
+    })
+    .await?;
+
+    let message = match params.dry_run {
+        true => "Dry run: no changes were committed.",
+        false => "Books tagged successfully!",
+    };
+    Ok(HttpResponse::Ok().json(json!({
+        "message": message,
+        "dry_run": params.dry_run,
+        "books_updated": books_updated
+    })))
+}
+
+// The first id gets rank 1. Every previously featured book that isn't in
+// the list loses its rank.
+pub async fn set_featured_books(
+    input: Json<Vec<Uuid>>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    let book_ids = input.into_inner();
+    let mut seen = HashSet::new();
+    if let Some(duplicate) = book_ids.iter().find(|id| !seen.insert(**id)) {
+        return Err(ApiError::bad_request(format!(
+            "Book '{}' is listed more than once.",
+            duplicate
+        )));
+    }
+
+    let book_ids = &book_ids;
+    in_transaction(
+        "set_featured_books",
+        &db_pool.0,
+        |mut transaction| async move {
+            // Clearing first keeps the partial unique index on featured_rank happy
+            // while ranks move between books.
+            sqlx::query!("UPDATE books SET featured_rank = NULL WHERE featured_rank IS NOT NULL")
+                .execute(&mut transaction)
+                .await?;
+
+            let result = sqlx::query!(
+                "UPDATE books SET featured_rank = featured.rank
+                FROM UNNEST($1::uuid[]) WITH ORDINALITY AS featured(id, rank)
+                WHERE books.id = featured.id",
+                book_ids
+            )
+            .execute(&mut transaction)
+            .await?;
+
+            if result.rows_affected() != book_ids.len() as u64 {
+                return Err(ApiError::bad_request(
+                    "Some of the listed books do not exist.",
+                ));
+            }
+
+            transaction.commit().await?;
+            Ok(())
+        },
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "message": "Featured books updated successfully!",
+        "featured": book_ids.len()
+    })))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupAuthor {
+    id: Uuid,
+    name: String,
+    nationality: String,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupBook {
+    id: Uuid,
+    title: String,
+    genre: String,
+    author_id: Uuid,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    #[serde(default)]
+    featured_rank: Option<i32>,
+    #[serde(default)]
+    parent_book_id: Option<Uuid>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupAlias {
+    id: Uuid,
+    author_id: Uuid,
+    alias: String,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupUser {
+    id: Uuid,
+    name: String,
+    email: String,
+    created_at: DateTime<Utc>,
+    #[serde(default = "active_by_default")]
+    is_active: bool,
+}
+
+fn active_by_default() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+pub struct Backup {
+    authors: Vec<BackupAuthor>,
+    // Missing from backups taken before aliases existed.
+    #[serde(default)]
+    author_aliases: Vec<BackupAlias>,
+    books: Vec<BackupBook>,
+    users: Vec<BackupUser>,
+}
+
+pub async fn backup(db_pool: Data<WritePool>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .streaming(backup_stream(db_pool.0.clone()))
+}
+
+// All four tables are read from one snapshot, so a backup taken during
+// writes never holds a book whose author or parent it left out, which
+// `restore` would refuse. Dropping the stream rolls the transaction back.
+fn backup_stream(db_pool: TimedPool) -> impl Stream<Item = Result<Bytes, std::io::Error>> {
+    async_stream::stream! {
+        let mut transaction = match begin("backup", &db_pool).await {
+            Ok(transaction) => transaction,
+            Err(e) => {
+                yield Err(std::io::Error::other(e));
+                return;
+            }
+        };
+        if let Err(e) = sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+            .execute(&mut transaction)
+            .await
+        {
+            yield Err(std::io::Error::other(e));
+            return;
+        }
+
+        let authors = sqlx::query_as!(
+            BackupAuthor,
+            "SELECT id, name, nationality, created_at FROM authors ORDER BY created_at, id"
+        )
+        .fetch(&mut transaction);
+        for await chunk in json_array("{\"authors\":[", authors) {
+            yield chunk;
+        }
+
+        let aliases = sqlx::query_as!(
+            BackupAlias,
+            "SELECT id, author_id, alias, created_at FROM author_aliases ORDER BY created_at, id"
+        )
+        .fetch(&mut transaction);
+        for await chunk in json_array("],\"author_aliases\":[", aliases) {
+            yield chunk;
+        }
+
+        let books = sqlx::query_as!(
+            BackupBook,
+            "SELECT id, title, genre, author_id, created_at, updated_at, featured_rank, parent_book_id
+            FROM books
+            ORDER BY created_at, id"
+        )
+        .fetch(&mut transaction);
+        for await chunk in json_array("],\"books\":[", books) {
+            yield chunk;
+        }
+
+        let users = sqlx::query_as!(
+            BackupUser,
+            "SELECT id, name, email, created_at, is_active FROM users ORDER BY created_at, id"
+        )
+        .fetch(&mut transaction);
+        for await chunk in json_array("],\"users\":[", users) {
+            yield chunk;
+        }
+
+        if let Err(e) = transaction.commit().await {
+            yield Err(std::io::Error::other(e));
+            return;
+        }
+        yield Ok(Bytes::from_static(b"]}"));
+    }
+}
+
+// Emits `prefix` followed by the comma-separated rows, one chunk per row.
+fn json_array<'a, T: Serialize + Send + 'a>(
+    prefix: &'static str,
+    mut rows: BoxStream<'a, Result<T, sqlx::Error>>,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + 'a {
+    async_stream::stream! {
+        yield Ok(Bytes::from_static(prefix.as_bytes()));
+
+        let mut separator = "";
+        while let Some(row) = rows.next().await {
+            match row.map_err(std::io::Error::other).and_then(|row| {
+                serde_json::to_string(&row).map_err(std::io::Error::other)
+            }) {
+                Ok(row) => {
+                    yield Ok(Bytes::from(format!("{}{}", separator, row)));
+                    separator = ",";
+                }
+                Err(e) => {
+                    yield Err(e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RestoreParams {
+    #[serde(default)]
+    truncate: bool,
+}
+
+pub async fn restore(
+    // Plain `web::Json`: a strict-mode pass would hold the whole backup in
+    // memory twice.
+    input: web::Json<Backup>,
+    params: Query<RestoreParams>,
+    bulk_limits: Data<BulkLimits>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    for items in [
+        input.authors.len(),
+        input.author_aliases.len(),
+        input.books.len(),
+        input.users.len(),
+    ] {
+        BulkLimits::check(bulk_limits.restore, items).map_err(ApiError::bad_request)?;
+    }
+
+    let (backup, truncate) = (&*input, params.truncate);
+    in_transaction("restore", &db_pool.0, |mut transaction| async move {
+        match restore_backup(&mut transaction, backup, truncate).await {
+            Ok(()) => {}
+            // Left for `in_transaction` to re-run, rather than blamed on the backup.
+            Err(e) if is_conflict(&e) => return Err(e.into()),
+            Err(sqlx::Error::Database(e)) => {
+                return Err(ApiError::bad_request(format!("Invalid backup: {}", e)))
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        transaction.commit().await?;
+        Ok(())
+    })
+    .await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "message": "Backup restored successfully!",
+        "authors": input.authors.len(),
+        "author_aliases": input.author_aliases.len(),
+        "books": input.books.len(),
+        "users": input.users.len()
+    })))
+}
+
+async fn restore_backup(
+    transaction: &mut TimedTransaction,
+    backup: &Backup,
+    truncate: bool,
+) -> Result<(), sqlx::Error> {
+    if truncate {
+        sqlx::query!("TRUNCATE books, author_aliases, authors, users")
+            .execute(&mut *transaction)
+            .await?;
+    }
+
+    sqlx::query!(
+        "INSERT INTO authors (id, name, nationality, created_at)
+        SELECT * FROM UNNEST($1::uuid[], $2::text[], $3::text[], $4::timestamptz[])
+        ON CONFLICT (id) DO UPDATE SET
+            name = EXCLUDED.name,
+            nationality = EXCLUDED.nationality,
+            created_at = EXCLUDED.created_at",
+        &backup.authors.iter().map(|a| a.id).collect::<Vec<_>>(),
+        &backup
+            .authors
+            .iter()
+            .map(|a| a.name.clone())
+            .collect::<Vec<_>>(),
+        &backup
+            .authors
+            .iter()
+            .map(|a| a.nationality.clone())
+            .collect::<Vec<_>>(),
+        &backup
+            .authors
+            .iter()
+            .map(|a| a.created_at)
+            .collect::<Vec<_>>()
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        "INSERT INTO author_aliases (id, author_id, alias, created_at)
+        SELECT * FROM UNNEST($1::uuid[], $2::uuid[], $3::text[], $4::timestamptz[])
+        ON CONFLICT (id) DO UPDATE SET
+            author_id = EXCLUDED.author_id,
+            alias = EXCLUDED.alias,
+            created_at = EXCLUDED.created_at",
+        &backup
+            .author_aliases
+            .iter()
+            .map(|a| a.id)
+            .collect::<Vec<_>>(),
+        &backup
+            .author_aliases
+            .iter()
+            .map(|a| a.author_id)
+            .collect::<Vec<_>>(),
+        &backup
+            .author_aliases
+            .iter()
+            .map(|a| a.alias.clone())
+            .collect::<Vec<_>>(),
+        &backup
+            .author_aliases
+            .iter()
+            .map(|a| a.created_at)
+            .collect::<Vec<_>>()
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        "INSERT INTO books (
+            id, title, genre, author_id, created_at, updated_at, featured_rank, parent_book_id
+        )
+        SELECT * FROM UNNEST(
+            $1::uuid[], $2::text[], $3::text[], $4::uuid[], $5::timestamptz[], $6::timestamptz[],
+            $7::int[], $8::uuid[]
+        )
+        ON CONFLICT (id) DO UPDATE SET
+            title = EXCLUDED.title,
+            genre = EXCLUDED.genre,
+            author_id = EXCLUDED.author_id,
+            created_at = EXCLUDED.created_at,
+            featured_rank = EXCLUDED.featured_rank,
+            parent_book_id = EXCLUDED.parent_book_id",
+        &backup.books.iter().map(|b| b.id).collect::<Vec<_>>(),
+        &backup
+            .books
+            .iter()
+            .map(|b| b.title.clone())
+            .collect::<Vec<_>>(),
+        &backup
+            .books
+            .iter()
+            .map(|b| b.genre.clone())
+            .collect::<Vec<_>>(),
+        &backup.books.iter().map(|b| b.author_id).collect::<Vec<_>>(),
+        &backup
+            .books
+            .iter()
+            .map(|b| b.created_at)
+            .collect::<Vec<_>>(),
+        &backup
+            .books
+            .iter()
+            .map(|b| b.updated_at)
+            .collect::<Vec<_>>(),
+        &backup
+            .books
+            .iter()
+            .map(|b| b.featured_rank)
+            .collect::<Vec<_>>() as &[Option<i32>],
+        &backup
+            .books
+            .iter()
+            .map(|b| b.parent_book_id)
+            .collect::<Vec<_>>() as &[Option<Uuid>]
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        "INSERT INTO users (id, name, email, created_at, is_active)
+        SELECT * FROM UNNEST(
+            $1::uuid[], $2::text[], $3::text[], $4::timestamptz[], $5::bool[]
+        )
+        ON CONFLICT (id) DO UPDATE SET
+            name = EXCLUDED.name,
+            email = EXCLUDED.email,
+            created_at = EXCLUDED.created_at,
+            is_active = EXCLUDED.is_active",
+        &backup.users.iter().map(|u| u.id).collect::<Vec<_>>(),
+        &backup
+            .users
+            .iter()
+            .map(|u| u.name.clone())
+            .collect::<Vec<_>>(),
+        &backup
+            .users
+            .iter()
+            .map(|u| u.email.clone())
+            .collect::<Vec<_>>(),
+        &backup
+            .users
+            .iter()
+            .map(|u| u.created_at)
+            .collect::<Vec<_>>(),
+        &backup.users.iter().map(|u| u.is_active).collect::<Vec<_>>()
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    Ok(())
+}
@@ -1,19 +1,223 @@
-use crate::validations::author::NewAuthor;
+use crate::{
+    configuration::{BulkLimits, FeatureFlags},
+    csv::{accepts_csv, csv_row},
+    db::{begin, in_transaction, retrying, ReadPool, TimedPool, WritePool},
+    errors::ApiError,
+    json::{self, Json, StrictJson},
+    locale::Locale,
+    ndjson::{accepts_ndjson, ndjson_line, NDJSON_CONTENT_TYPE},
+    pagination::{PageQuery, Paginated},
+    routes::{
+        api_route,
+        books::{delete_book_error, is_title_conflict},
+        contains_pattern, parse_uuid_path, ApiRoute, LEGACY_ROUTES_SUNSET,
+    },
+    sorting::{DefaultSorts, SortOrder, SortQuery, AUTHOR_SORT_COLUMNS},
+    timezone::{localize, localize_rfc3339, TimezoneQuery},
+    validations::{
+        author::{AuthorUpdate, NewAuthor, ValidatedAuthorName},
+        ValidationError,
+    },
+};
 use actix_web::{
-    web::{Data, Json, Path},
-    HttpResponse,
+    http::header,
+    web::{Bytes, Data, Path, Query},
+    HttpRequest, HttpResponse,
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use futures_util::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use sqlx::PgPool;
+use sqlx::PgExecutor;
+use std::collections::HashMap;
 use uuid::Uuid;
 
-pub async fn authors_index(db_pool: Data<PgPool>) -> HttpResponse {
-    let rows = sqlx::query!("SELECT id, name, nationality, created_at FROM authors")
-        .fetch_all(db_pool.get_ref())
-        .await
-        .expect("Failed to fetch saved authors.");
+pub fn routes(features: &FeatureFlags) -> Vec<ApiRoute> {
+    let mut routes = vec![
+        api_route("GET", "/authors", "List all authors", authors_index)
+            .route("POST", "Create an author", create_author)
+            .idempotent(),
+    ];
+    if features.is_enabled("search") {
+        routes.push(api_route(
+            "GET",
+            "/authors/search",
+            "Search authors by name",
+            search_authors,
+        ));
+    } else {
+        routes.push(ApiRoute::switched_off("/authors/search"));
+    }
+    routes.push(api_route(
+        "GET",
+        "/authors/exists",
+        "Check whether an author name is taken",
+        author_exists,
+    ));
+    routes.push(api_route(
+        "GET",
+        "/authors/by-name/{name}",
+        "Find an author by exact name",
+        author_by_name,
+    ));
+    routes.push(api_route(
+        "GET",
+        "/authors/book-counts",
+        "Rank authors by number of books",
+        author_book_counts,
+    ));
+    routes.push(api_route(
+        "POST",
+        "/authors/batch",
+        "Fetch several authors with their books",
+        batch_authors,
+    ));
+    if features.is_enabled("legacy_routes") {
+        routes.push(
+            api_route("POST", "/authors/create", "Create an author", create_author)
+                .deprecated(LEGACY_ROUTES_SUNSET, "POST /authors")
+                .idempotent(),
+        );
+        routes.push(
+            api_route("POST", "/authors/delete", "Delete an author", delete_author)
+                .deprecated(LEGACY_ROUTES_SUNSET, "DELETE /authors/{author_id}"),
+        );
+    }
+    routes.push(api_route(
+        "POST",
+        "/authors/bulk_create",
+        "Create several authors at once",
+        bulk_create_authors,
+    ));
+    routes.push(
+        api_route("GET", "/authors/{author_id}", "Show an author", show_author)
+            .route(
+                "PUT",
+                "Update an author's name or nationality",
+                update_author,
+            )
+            .route("DELETE", "Delete an author", delete_author_by_id),
+    );
+    routes.push(api_route(
+        "POST",
+        "/authors/{author_id}/aliases",
+        "Add a pen name to an author",
+        add_author_alias,
+    ));
+    routes.push(api_route(
+        "DELETE",
+        "/authors/{author_id}/aliases/{alias_id}",
+        "Remove a pen name from an author",
+        remove_author_alias,
+    ));
+    routes.push(api_route(
+        "GET",
+        "/authors/{author_id}/books",
+        "List an author's books, a page at a time",
+        author_books,
+    ));
+    routes.push(api_route(
+        "GET",
+        "/authors/{author_id}/similar",
+        "List authors writing in the same genres",
+        similar_authors,
+    ));
+    if features.is_enabled("export") {
+        routes.push(api_route(
+            "GET",
+            "/authors/{author_id}/export",
+            "Export an author with their books",
+            export_author,
+        ));
+    }
+    routes.push(api_route(
+        "POST",
+        "/authors/{author_id}/transfer_books",
+        "Move an author's books to another author",
+        transfer_books,
+    ));
+    routes.push(api_route(
+        "GET",
+        "/nationalities/stats",
+        "Count authors per nationality",
+        nationality_stats,
+    ));
+    if features.is_enabled("seeding") {
+        routes.push(api_route(
+            "GET",
+            "/seed_authors",
+            "Seed the database with sample authors",
+            seed_authors,
+        ));
+    }
+    routes
+}
+
+#[derive(Deserialize)]
+pub struct AuthorFilterQuery {
+    has_books: Option<bool>,
+}
+
+pub async fn authors_index(
+    req: HttpRequest,
+    query: Query<TimezoneQuery>,
+    sort_query: Query<SortQuery>,
+    filter: Query<AuthorFilterQuery>,
+    page_query: Query<PageQuery>,
+    default_sorts: Data<DefaultSorts>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+    let sort = sort_query
+        .parse(&AUTHOR_SORT_COLUMNS, &default_sorts.authors)
+        .map_err(ApiError::bad_request)?;
+    let page = page_query.parse().map_err(ApiError::bad_request)?;
+
+    if accepts_ndjson(&req) {
+        return Ok(HttpResponse::Ok()
+            .content_type(NDJSON_CONTENT_TYPE)
+            .streaming(authors_ndjson_stream(
+                db_pool.0.clone(),
+                tz,
+                sort,
+                filter.into_inner(),
+            )));
+    }
+
+    let rows = retrying("authors_index", || {
+        sqlx::query!(
+            "SELECT id, name, nationality, created_at FROM authors
+            WHERE $2::bool IS NULL
+                OR EXISTS (SELECT 1 FROM books WHERE books.author_id = authors.id) = $2
+            ORDER BY
+                CASE WHEN $1 = 'name' THEN name END ASC,
+                CASE WHEN $1 = '-name' THEN name END DESC,
+                CASE WHEN $1 = 'nationality' THEN nationality END ASC,
+                CASE WHEN $1 = '-nationality' THEN nationality END DESC,
+                CASE WHEN $1 = '-created_at' THEN created_at END DESC,
+                created_at ASC,
+                id ASC
+            LIMIT $3 OFFSET $4",
+            sort.as_ref(),
+            filter.has_books,
+            page.per_page,
+            page.offset()
+        )
+        .fetch_all(&db_pool.0)
+    })
+    .await?;
+    let total = retrying("authors_index.total", || {
+        sqlx::query!(
+            r#"SELECT COUNT(*) AS "total!" FROM authors
+            WHERE $1::bool IS NULL
+                OR EXISTS (SELECT 1 FROM books WHERE books.author_id = authors.id) = $1"#,
+            filter.has_books
+        )
+        .fetch_one(&db_pool.0)
+    })
+    .await?
+    .total;
 
     let authors: Vec<serde_json::Value> = rows
         .into_iter()
@@ -22,36 +226,584 @@ pub async fn authors_index(db_pool: Data<PgPool>) -> HttpResponse {
                 "id": row.id,
                 "name": row.name,
                 "nationality": row.nationality,
-                "created_at": row.created_at
+                "created_at": localize(row.created_at, tz)
             })
         })
         .collect();
 
-    HttpResponse::Ok().json(authors)
+    Ok(HttpResponse::Ok()
+        .page_headers(&req, page, total)
+        .json(authors))
 }
 
-pub async fn show_author(input: Path<String>, db_pool: Data<PgPool>) -> HttpResponse {
-    let author_id = input.into_inner();
+fn authors_ndjson_stream(
+    db_pool: TimedPool,
+    tz: Option<Tz>,
+    sort: SortOrder,
+    filter: AuthorFilterQuery,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> {
+    async_stream::stream! {
+        let mut rows = sqlx::query!(
+            "SELECT id, name, nationality, created_at FROM authors
+            WHERE $2::bool IS NULL
+                OR EXISTS (SELECT 1 FROM books WHERE books.author_id = authors.id) = $2
+            ORDER BY
+                CASE WHEN $1 = 'name' THEN name END ASC,
+                CASE WHEN $1 = '-name' THEN name END DESC,
+                CASE WHEN $1 = 'nationality' THEN nationality END ASC,
+                CASE WHEN $1 = '-nationality' THEN nationality END DESC,
+                CASE WHEN $1 = '-created_at' THEN created_at END DESC,
+                created_at ASC,
+                id ASC",
+            sort.as_ref(),
+            filter.has_books
+        )
+        .fetch(db_pool.named("authors_ndjson"));
 
-    match sqlx::query!(
-        "SELECT id, name, nationality, created_at FROM authors WHERE id = $1",
-        Uuid::parse_str(&author_id).unwrap_or_default()
+        while let Some(row) = rows.next().await {
+            match row {
+                Ok(author) => {
+                    yield Ok(ndjson_line(&json!({
+                        "id": author.id,
+                        "name": author.name,
+                        "nationality": author.nationality,
+                        "created_at": localize(author.created_at, tz)
+                    })))
+                }
+                Err(e) => {
+                    yield Err(std::io::Error::other(e));
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ShowAuthorParams {
+    include: Option<String>,
+}
+
+pub async fn show_author(
+    input: Path<String>,
+    query: Query<TimezoneQuery>,
+    params: Query<ShowAuthorParams>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let author_id = parse_uuid_path(&input)?;
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+    let include_latest_book = match params.include.as_deref() {
+        None => false,
+        Some("latest_book") => true,
+        Some(include) => {
+            return Err(ApiError::bad_request(format!(
+                "'{}' is not a supported include.",
+                include
+            )))
+        }
+    };
+
+    let author = retrying("show_author", || {
+        sqlx::query!(
+            r#"
+            SELECT
+                authors.id,
+                authors.name,
+                authors.nationality,
+                authors.created_at,
+                latest_book.id AS "latest_book_id?",
+                latest_book.title AS "latest_book_title?",
+                latest_book.genre AS "latest_book_genre?",
+                latest_book.created_at AS "latest_book_created_at?",
+                ARRAY(
+                    SELECT alias FROM author_aliases
+                    WHERE author_aliases.author_id = authors.id
+                    ORDER BY lower(alias)
+                ) AS "aliases!"
+            FROM authors
+            LEFT JOIN LATERAL (
+                SELECT id, title, genre, created_at
+                FROM books
+                WHERE books.author_id = authors.id
+                ORDER BY created_at DESC, id DESC
+                LIMIT 1
+            ) latest_book ON true
+            WHERE authors.id = $1
+            "#,
+            author_id
+        )
+        .fetch_optional(&db_pool.0)
+    })
+    .await?
+    .ok_or(ApiError::NotFound("Author not found"))?;
+
+    let mut author_json = json!({
+        "id": author.id,
+        "name": author.name,
+        "nationality": author.nationality,
+        "aliases": author.aliases,
+        "created_at": localize(author.created_at, tz)
+    });
+
+    if include_latest_book {
+        author_json["latest_book"] = match (
+            author.latest_book_id,
+            author.latest_book_title,
+            author.latest_book_genre,
+            author.latest_book_created_at,
+        ) {
+            (Some(id), Some(title), Some(genre), Some(created_at)) => json!({
+                "id": id,
+                "title": title,
+                "genre": genre,
+                "created_at": localize(created_at, tz)
+            }),
+            _ => Value::Null,
+        };
+    }
+
+    Ok(HttpResponse::Ok().json(author_json))
+}
+
+pub async fn author_books(
+    req: HttpRequest,
+    input: Path<String>,
+    query: Query<TimezoneQuery>,
+    page_query: Query<PageQuery>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let author_id = parse_uuid_path(&input)?;
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+    let page = page_query.parse().map_err(ApiError::bad_request)?;
+
+    let total = retrying("author_books.total", || {
+        sqlx::query!(
+            r#"
+            SELECT (SELECT COUNT(*) FROM books WHERE author_id = $1) AS "total!"
+            FROM authors
+            WHERE id = $1
+            "#,
+            author_id
+        )
+        .fetch_optional(&db_pool.0)
+    })
+    .await?
+    .ok_or(ApiError::NotFound("Author not found"))?
+    .total;
+
+    let rows = retrying("author_books", || {
+        sqlx::query!(
+            "SELECT id, title, genre, created_at FROM books
+            WHERE author_id = $1
+            ORDER BY created_at ASC, id ASC
+            LIMIT $2 OFFSET $3",
+            author_id,
+            page.per_page,
+            page.offset()
+        )
+        .fetch_all(&db_pool.0)
+    })
+    .await?;
+    let books: Vec<Value> = rows
+        .into_iter()
+        .map(|row| {
+            json!({
+                "id": row.id,
+                "title": row.title,
+                "genre": row.genre,
+                "created_at": localize(row.created_at, tz)
+            })
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok()
+        .page_headers(&req, page, total)
+        .json(books))
+}
+
+const DEFAULT_SIMILAR_AUTHORS_LIMIT: i64 = 10;
+const MAX_SIMILAR_AUTHORS_LIMIT: i64 = 50;
+
+#[derive(Deserialize)]
+pub struct SimilarAuthorsParams {
+    limit: Option<i64>,
+}
+
+pub async fn similar_authors(
+    input: Path<String>,
+    params: Query<SimilarAuthorsParams>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let author_id = parse_uuid_path(&input)?;
+    let limit = params.limit.unwrap_or(DEFAULT_SIMILAR_AUTHORS_LIMIT);
+    if limit < 1 {
+        return Err(ApiError::bad_request(format!(
+            "'{}' is not a valid limit.",
+            limit
+        )));
+    }
+
+    let author = retrying("similar_authors.author", || {
+        sqlx::query!(
+            r#"SELECT EXISTS(SELECT 1 FROM authors WHERE id = $1) AS "exists!""#,
+            author_id
+        )
+        .fetch_one(&db_pool.0)
+    })
+    .await?;
+    if !author.exists {
+        return Err(ApiError::NotFound("Author not found"));
+    }
+
+    let rows = retrying("similar_authors", || {
+        sqlx::query!(
+            r#"
+            WITH author_genres AS (
+                SELECT DISTINCT genre FROM books WHERE author_id = $1
+            )
+            SELECT
+                authors.id,
+                authors.name,
+                authors.nationality,
+                ARRAY_AGG(DISTINCT books.genre ORDER BY books.genre) AS "shared_genres!",
+                COUNT(DISTINCT books.genre) AS "shared_genres_count!"
+            FROM authors
+            JOIN books ON books.author_id = authors.id
+            JOIN author_genres ON author_genres.genre = books.genre
+            WHERE authors.id <> $1
+            GROUP BY authors.id
+            ORDER BY COUNT(DISTINCT books.genre) DESC, authors.name ASC, authors.id ASC
+            LIMIT $2
+            "#,
+            author_id,
+            limit.min(MAX_SIMILAR_AUTHORS_LIMIT)
+        )
+        .fetch_all(&db_pool.0)
+    })
+    .await?;
+    let authors: Vec<Value> = rows
+        .into_iter()
+        .map(|row| {
+            json!({
+                "id": row.id,
+                "name": row.name,
+                "nationality": row.nationality,
+                "shared_genres": row.shared_genres,
+                "shared_genres_count": row.shared_genres_count
+            })
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(authors))
+}
+
+#[derive(Deserialize)]
+pub struct AuthorExistsParams {
+    name: String,
+}
+
+pub async fn author_exists(
+    params: Query<AuthorExistsParams>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let record = retrying("author_exists", || {
+        sqlx::query!(
+            r#"SELECT EXISTS(SELECT 1 FROM authors WHERE lower(name) = lower($1)) AS "exists!""#,
+            params.name.trim()
+        )
+        .fetch_one(&db_pool.0)
+    })
+    .await?;
+
+    Ok(HttpResponse::Ok().json(json!({"exists": record.exists})))
+}
+
+pub async fn author_by_name(
+    input: Path<String>,
+    query: Query<TimezoneQuery>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+
+    let rows = retrying("author_by_name", || {
+        sqlx::query!(
+            "SELECT id, name, nationality, created_at FROM authors
+            WHERE lower(name) = lower($1)
+            ORDER BY created_at ASC, id ASC",
+            input.trim()
+        )
+        .fetch_all(&db_pool.0)
+    })
+    .await?;
+
+    // Names are not unique yet, so an ambiguous name is answered with the
+    // candidate ids instead of silently picking one of them.
+    match rows.as_slice() {
+        [] => Err(ApiError::NotFound("Author not found")),
+        [author] => Ok(HttpResponse::Ok().json(json!({
+            "id": author.id,
+            "name": author.name,
+            "nationality": author.nationality,
+            "created_at": localize(author.created_at, tz)
+        }))),
+        authors => Ok(HttpResponse::MultipleChoices().json(json!({
+            "message": "Multiple authors share this name",
+            "author_ids": authors.iter().map(|author| author.id).collect::<Vec<Uuid>>()
+        }))),
+    }
+}
+
+const FUZZY_SIMILARITY_THRESHOLD: f32 = 0.3;
+
+#[derive(Deserialize)]
+pub struct AuthorSearchParams {
+    q: String,
+    #[serde(default)]
+    fuzzy: bool,
+}
+
+pub async fn search_authors(
+    params: Query<AuthorSearchParams>,
+    query: Query<TimezoneQuery>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+    let term = params.q.trim();
+    if term.is_empty() {
+        return Err(ApiError::bad_request("Search query must not be empty."));
+    }
+
+    if !params.fuzzy {
+        let pattern = contains_pattern(term);
+
+        let rows = retrying("search_authors", || {
+            sqlx::query!(
+                "SELECT id, name, nationality, created_at FROM authors
+                WHERE name ILIKE $1
+                    OR EXISTS (
+                        SELECT 1 FROM author_aliases
+                        WHERE author_aliases.author_id = authors.id AND alias ILIKE $1
+                    )
+                ORDER BY name ASC, id ASC",
+                pattern
+            )
+            .fetch_all(&db_pool.0)
+        })
+        .await?;
+
+        return Ok(HttpResponse::Ok().json(
+            rows.into_iter()
+                .map(|row| {
+                    json!({
+                        "id": row.id,
+                        "name": row.name,
+                        "nationality": row.nationality,
+                        "created_at": localize(row.created_at, tz)
+                    })
+                })
+                .collect::<Vec<Value>>(),
+        ));
+    }
+
+    let mut transaction = begin("search_authors", &db_pool.0).await?;
+
+    sqlx::query!(
+        "SELECT set_config('pg_trgm.word_similarity_threshold', $1, true)",
+        FUZZY_SIMILARITY_THRESHOLD.to_string()
     )
-    .fetch_one(db_pool.get_ref())
-    .await
-    {
-        Ok(author) => {
-            let author_json = json!({
+    .fetch_one(&mut transaction)
+    .await?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            authors.id,
+            authors.name,
+            authors.nationality,
+            authors.created_at,
+            GREATEST(
+                word_similarity($1, authors.name),
+                MAX(word_similarity($1, author_aliases.alias))
+            ) AS "similarity!"
+        FROM authors
+        LEFT JOIN author_aliases ON author_aliases.author_id = authors.id
+        WHERE $1 <% authors.name OR $1 <% author_aliases.alias
+        GROUP BY authors.id
+        ORDER BY 5 DESC, authors.name ASC, authors.id ASC
+        "#,
+        term
+    )
+    .fetch_all(&mut transaction)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(
+        rows.into_iter()
+            .map(|row| {
+                json!({
+                    "id": row.id,
+                    "name": row.name,
+                    "nationality": row.nationality,
+                    "created_at": localize(row.created_at, tz),
+                    "similarity": row.similarity
+                })
+            })
+            .collect::<Vec<Value>>(),
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct ExportParams {
+    format: Option<String>,
+}
+
+pub async fn export_author(
+    input: Path<String>,
+    params: Query<ExportParams>,
+    query: Query<TimezoneQuery>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let author_id = parse_uuid_path(&input)?;
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+
+    let author = retrying("export_author.author", || {
+        sqlx::query!(
+            "SELECT id, name, nationality, created_at FROM authors WHERE id = $1",
+            author_id
+        )
+        .fetch_optional(&db_pool.0)
+    })
+    .await?
+    .ok_or(ApiError::NotFound("Author not found"))?;
+
+    let books = retrying("export_author.books", || {
+        sqlx::query!(
+            "SELECT id, title, genre, created_at FROM books
+            WHERE author_id = $1
+            ORDER BY created_at ASC, id ASC",
+            author.id
+        )
+        .fetch_all(&db_pool.0)
+    })
+    .await?;
+
+    match params.format.as_deref() {
+        None | Some("json") => Ok(HttpResponse::Ok().json(json!({
+            "id": author.id,
+            "name": author.name,
+            "nationality": author.nationality,
+            "created_at": localize(author.created_at, tz),
+            "books": books
+                .iter()
+                .map(|book| json!({
+                    "id": book.id,
+                    "title": book.title,
+                    "genre": book.genre,
+                    "created_at": localize(book.created_at, tz)
+                }))
+                .collect::<Vec<Value>>()
+        }))),
+        Some("csv") => {
+            let mut body = csv_row(&[
+                "author_id",
+                "author_name",
+                "author_nationality",
+                "book_id",
+                "book_title",
+                "book_genre",
+                "book_created_at",
+            ]);
+            for book in books.iter() {
+                body.push_str(&csv_row(&[
+                    &author.id.to_string(),
+                    &author.name,
+                    &author.nationality,
+                    &book.id.to_string(),
+                    &book.title,
+                    &book.genre,
+                    &localize_rfc3339(book.created_at, tz),
+                ]));
+            }
+
+            Ok(HttpResponse::Ok()
+                .content_type("text/csv; charset=utf-8")
+                .insert_header((
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"author-{}.csv\"", author.id),
+                ))
+                .body(body))
+        }
+        Some(format) => Err(ApiError::bad_request(format!(
+            "'{}' is not a supported export format.",
+            format
+        ))),
+    }
+}
+
+pub async fn batch_authors(
+    input: Json<Vec<String>>,
+    query: Query<TimezoneQuery>,
+    db_pool: Data<ReadPool>,
+    bulk_limits: Data<BulkLimits>,
+) -> Result<HttpResponse, ApiError> {
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+    BulkLimits::check(bulk_limits.authors_batch, input.len()).map_err(ApiError::bad_request)?;
+    let author_ids = input
+        .iter()
+        .map(|id| parse_uuid_path(id))
+        .collect::<Result<Vec<Uuid>, ApiError>>()?;
+
+    let authors = retrying("batch_authors.authors", || {
+        sqlx::query!(
+            "SELECT id, name, nationality, created_at FROM authors WHERE id = ANY($1)",
+            &author_ids
+        )
+        .fetch_all(&db_pool.0)
+    })
+    .await?;
+
+    let books = retrying("batch_authors.books", || {
+        sqlx::query!(
+            "SELECT id, title, genre, author_id, created_at FROM books
+            WHERE author_id = ANY($1)
+            ORDER BY created_at ASC, id ASC",
+            &author_ids
+        )
+        .fetch_all(&db_pool.0)
+    })
+    .await?;
+
+    let mut books_by_author: HashMap<Uuid, Vec<Value>> = HashMap::new();
+    for book in books {
+        books_by_author
+            .entry(book.author_id)
+            .or_default()
+            .push(json!({
+                "id": book.id,
+                "title": book.title,
+                "genre": book.genre,
+                "created_at": localize(book.created_at, tz)
+            }));
+    }
+    let authors_by_id: HashMap<Uuid, _> = authors
+        .into_iter()
+        .map(|author| (author.id, author))
+        .collect();
+
+    let result: Vec<Value> = author_ids
+        .iter()
+        .filter_map(|author_id| authors_by_id.get(author_id))
+        .map(|author| {
+            json!({
                 "id": author.id,
                 "name": author.name,
                 "nationality": author.nationality,
-                "created_at": author.created_at
-            });
+                "created_at": localize(author.created_at, tz),
+                "books": books_by_author.get(&author.id).cloned().unwrap_or_default()
+            })
+        })
+        .collect();
 
-            HttpResponse::Ok().json(author_json)
-        }
-        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
-    }
+    Ok(HttpResponse::Ok().json(result))
 }
 
 #[derive(Serialize, Deserialize)]
@@ -60,68 +812,503 @@ pub struct NewAuthorData {
     pub nationality: String,
 }
 
-pub async fn create_author(input: Json<NewAuthorData>, db_pool: Data<PgPool>) -> HttpResponse {
-    let new_author: NewAuthor = match input.0.try_into() {
-        Ok(value) => value,
-        Err(error) => return HttpResponse::BadRequest().body(error),
-    };
+pub async fn create_author(
+    req: HttpRequest,
+    input: Json<NewAuthorData>,
+    query: Query<TimezoneQuery>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+    let new_author: NewAuthor = input
+        .0
+        .try_into()
+        .map_err(|errors| ApiError::invalid(&req, errors))?;
+    let author = insert_author(&new_author, db_pool.0.named("create_author")).await?;
+    let mut body = json!(author);
+    body["created_at"] = localize(author.created_at, tz);
 
-    match sqlx::query!(
+    Ok(HttpResponse::Created()
+        .insert_header((header::LOCATION, format!("/authors/{}", author.id)))
+        .json(body))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UpdateAuthorData {
+    pub name: Option<String>,
+    pub nationality: Option<String>,
+}
+
+pub async fn update_author(
+    req: HttpRequest,
+    input: Path<String>,
+    body: Json<UpdateAuthorData>,
+    query: Query<TimezoneQuery>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    let author_id = parse_uuid_path(&input)?;
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+    let update: AuthorUpdate = body
+        .into_inner()
+        .try_into()
+        .map_err(|errors| ApiError::invalid(&req, errors))?;
+    if update.is_empty() {
+        return Err(ApiError::bad_request(
+            "Nothing to update; send a 'name' or a 'nationality'.",
+        ));
+    }
+
+    let author = update_author_record(author_id, &update, db_pool.0.named("update_author"))
+        .await?
+        .ok_or(ApiError::NotFound("Author not found"))?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "id": author.id,
+        "name": author.name,
+        "nationality": author.nationality,
+        "created_at": localize(author.created_at, tz)
+    })))
+}
+
+/// An author as just written, answered by the create and update endpoints.
+#[derive(Serialize)]
+pub struct CreatedAuthor {
+    pub id: Uuid,
+    pub name: String,
+    pub nationality: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Applies `update` and answers the author as they now are, or `None` when
+/// there is no such author.
+pub(crate) async fn update_author_record(
+    author_id: Uuid,
+    update: &AuthorUpdate,
+    executor: impl PgExecutor<'_>,
+) -> Result<Option<CreatedAuthor>, sqlx::Error> {
+    sqlx::query_as!(
+        CreatedAuthor,
+        "UPDATE authors
+        SET name = COALESCE($2, name), nationality = COALESCE($3, nationality)
+        WHERE id = $1
+        RETURNING id, name, nationality, created_at",
+        author_id,
+        update.name.as_ref().map(AsRef::as_ref),
+        update.nationality.as_ref().map(AsRef::as_ref)
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+pub(crate) async fn insert_author(
+    new_author: &NewAuthor,
+    executor: impl PgExecutor<'_>,
+) -> Result<CreatedAuthor, sqlx::Error> {
+    sqlx::query_as!(
+        CreatedAuthor,
         "INSERT INTO authors (name, nationality, created_at)
         VALUES ($1, $2, $3)
-        RETURNING id",
+        RETURNING id, name, nationality, created_at",
         new_author.name.as_ref(),
         new_author.nationality.as_ref(),
         Utc::now()
     )
-    .fetch_one(db_pool.get_ref())
+    .fetch_one(executor)
     .await
-    {
-        Ok(record) => HttpResponse::Ok().json(json!({
-            "message": "Author created successfully!",
-            "author_id": record.id
-        })),
-        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+}
+
+#[derive(Deserialize)]
+pub struct BulkCreateParams {
+    #[serde(default)]
+    partial: bool,
+}
+
+pub async fn bulk_create_authors(
+    req: HttpRequest,
+    input: Json<Vec<Value>>,
+    params: Query<BulkCreateParams>,
+    query: Query<TimezoneQuery>,
+    bulk_limits: Data<BulkLimits>,
+    strict_json: Data<StrictJson>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+    BulkLimits::check(bulk_limits.authors_bulk_create, input.len())
+        .map_err(ApiError::bad_request)?;
+    let locale = Locale::negotiate(&req);
+    let mut valid_authors = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, item) in input.into_inner().into_iter().enumerate() {
+        let new_author: Result<NewAuthor, Vec<ValidationError>> =
+            json::from_value::<NewAuthorData>(item, strict_json.0)
+                .map_err(|e| vec![ValidationError::new("malformed_author", e)])
+                .and_then(|data| data.try_into());
+
+        match new_author {
+            Ok(author) => valid_authors.push((index, author)),
+            Err(item_errors) => errors.extend(item_errors.iter().map(|error| {
+                let mut error = error.to_json(locale);
+                error["index"] = index.into();
+                error
+            })),
+        }
+    }
+
+    if !params.partial && !errors.is_empty() {
+        return Ok(
+            HttpResponse::UnprocessableEntity().json(json!({"created": [], "errors": errors}))
+        );
+    }
+
+    let valid_authors = &valid_authors;
+    let (created, created_rows) = in_transaction(
+        "bulk_create_authors",
+        &db_pool.0,
+        move |mut transaction| async move {
+            let mut created = Vec::new();
+            let mut created_rows = csv_row(&["id", "name", "nationality"]);
+            for (index, new_author) in valid_authors {
+                let author = insert_author(new_author, &mut transaction).await?;
+                created_rows.push_str(&csv_row(&[
+                    &author.id.to_string(),
+                    &author.name,
+                    &author.nationality,
+                ]));
+                let mut created_author = json!(author);
+                created_author["created_at"] = localize(author.created_at, tz);
+                created_author["index"] = (*index).into();
+                created.push(created_author);
+            }
+
+            transaction.commit().await?;
+            Ok::<_, ApiError>((created, created_rows))
+        },
+    )
+    .await?;
+
+    // CSV has nowhere to put per-item errors, so a partial success stays JSON.
+    if errors.is_empty() && accepts_csv(&req) {
+        return Ok(HttpResponse::Created()
+            .content_type("text/csv; charset=utf-8")
+            .body(created_rows));
+    }
+
+    let body = json!({"created": created, "errors": errors});
+    if errors.is_empty() {
+        Ok(HttpResponse::Created().json(body))
+    } else {
+        Ok(HttpResponse::MultiStatus().json(body))
     }
 }
 
 #[derive(Deserialize)]
-pub struct AuthorId {
-    id: String,
+pub struct TransferBooksData {
+    target_id: String,
+}
+
+pub async fn transfer_books(
+    input: Path<String>,
+    body: Json<TransferBooksData>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    let source_id = parse_uuid_path(&input)?;
+    let target_id = parse_uuid_path(&body.target_id)?;
+
+    if source_id == target_id {
+        return Err(ApiError::bad_request(
+            "Cannot transfer books to the same author.",
+        ));
+    }
+
+    let books_moved = in_transaction("transfer_books", &db_pool.0, |mut transaction| async move {
+        let found_authors = sqlx::query!(
+            r#"SELECT COUNT(*) AS "count!" FROM authors WHERE id = $1 OR id = $2"#,
+            source_id,
+            target_id
+        )
+        .fetch_one(&mut transaction)
+        .await?
+        .count;
+
+        if found_authors != 2 {
+            return Err(ApiError::NotFound("Author not found"));
+        }
+
+        let result = sqlx::query!(
+            "UPDATE books SET author_id = $2 WHERE author_id = $1",
+            source_id,
+            target_id
+        )
+        .execute(&mut transaction)
+        .await
+        .map_err(|e| {
+            if is_title_conflict(&e) {
+                ApiError::Conflict(
+                    "The target author already has a book with the title of one being transferred.",
+                )
+            } else {
+                e.into()
+            }
+        })?;
+
+        transaction.commit().await?;
+        Ok(result.rows_affected())
+    })
+    .await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "message": "Books transferred successfully!",
+        "books_moved": books_moved
+    })))
 }
 
-pub async fn delete_author(input: Json<AuthorId>, db_pool: Data<PgPool>) -> HttpResponse {
+#[derive(Deserialize)]
+pub struct NewAliasData {
+    alias: String,
+}
+
+pub async fn add_author_alias(
+    req: HttpRequest,
+    input: Path<String>,
+    body: Json<NewAliasData>,
+    query: Query<TimezoneQuery>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    let author_id = parse_uuid_path(&input)?;
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+    // Pen names follow the same rules as author names.
+    let alias = ValidatedAuthorName::new(body.into_inner().alias)
+        .map_err(|error| ApiError::invalid(&req, [error]))?;
+
     match sqlx::query!(
-        "DELETE FROM authors WHERE id = $1",
-        Uuid::parse_str(&input.id).unwrap_or_default(),
+        "INSERT INTO author_aliases (author_id, alias, created_at)
+        VALUES ($1, $2, $3)
+        RETURNING id, author_id, alias, created_at",
+        author_id,
+        alias.as_ref(),
+        Utc::now()
     )
-    .execute(db_pool.get_ref())
+    .fetch_one(db_pool.0.named("add_author_alias"))
     .await
     {
-        Ok(result) => match result.rows_affected() == 1 {
-            true => HttpResponse::Ok().json(json!({"message": "Author deleted successfully!"})),
-            false => {
-                HttpResponse::NotFound().json(json!({"message": "Author to be deleted not found"}))
+        Ok(alias) => Ok(HttpResponse::Created()
+            .insert_header((
+                header::LOCATION,
+                format!("/authors/{}/aliases/{}", alias.author_id, alias.id),
+            ))
+            .json(json!({
+                "id": alias.id,
+                "author_id": alias.author_id,
+                "alias": alias.alias,
+                "created_at": localize(alias.created_at, tz)
+            }))),
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+            Err(ApiError::NotFound("Author not found"))
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            Err(ApiError::Conflict("This author already has that alias."))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn remove_author_alias(
+    input: Path<(String, String)>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    let (author_id, alias_id) = input.into_inner();
+    let (author_id, alias_id) = (parse_uuid_path(&author_id)?, parse_uuid_path(&alias_id)?);
+
+    let result = sqlx::query!(
+        "DELETE FROM author_aliases WHERE id = $1 AND author_id = $2",
+        alias_id,
+        author_id
+    )
+    .execute(db_pool.0.named("remove_author_alias"))
+    .await?;
+
+    if result.rows_affected() != 1 {
+        return Err(ApiError::NotFound("Alias not found"));
+    }
+    Ok(HttpResponse::Ok().json(json!({"message": "Alias removed successfully!"})))
+}
+
+#[derive(Deserialize)]
+pub struct AuthorId {
+    id: String,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteAuthorParams {
+    #[serde(default)]
+    cascade: bool,
+}
+
+pub async fn delete_author(
+    input: Json<AuthorId>,
+    params: Query<DeleteAuthorParams>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    remove_author(parse_uuid_path(&input.id)?, params.cascade, &db_pool).await
+}
+
+pub async fn delete_author_by_id(
+    input: Path<String>,
+    params: Query<DeleteAuthorParams>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    remove_author(parse_uuid_path(&input)?, params.cascade, &db_pool).await
+}
+
+// Books are never removed as a side effect: without `cascade` the foreign
+// key on books.author_id turns the delete into a 409.
+async fn remove_author(
+    author_id: Uuid,
+    cascade: bool,
+    db_pool: &WritePool,
+) -> Result<HttpResponse, ApiError> {
+    let books_deleted = in_transaction("delete_author", &db_pool.0, |mut transaction| async move {
+        let books_deleted = match cascade {
+            true => sqlx::query!("DELETE FROM books WHERE author_id = $1", author_id)
+                .execute(&mut transaction)
+                .await
+                // Another author's edition of one of these books keeps it.
+                .map_err(delete_book_error)?
+                .rows_affected(),
+            false => 0,
+        };
+
+        match delete_author_record(author_id, &mut transaction).await {
+            Ok(true) => {}
+            Ok(false) => return Err(ApiError::NotFound("Author to be deleted not found")),
+            Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+                return Err(ApiError::Conflict(
+                    "This author still has books; pass ?cascade=true to delete them too.",
+                ))
             }
-        },
-        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+            Err(e) => return Err(e.into()),
+        }
+        transaction.commit().await?;
+        Ok(books_deleted)
+    })
+    .await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "message": "Author deleted successfully!",
+        "books_deleted": books_deleted
+    })))
+}
+
+/// The error for an author who still has books where there is no
+/// `cascade` to offer, as in GraphQL.
+pub(crate) fn author_has_books_error(error: sqlx::Error) -> ApiError {
+    match error {
+        sqlx::Error::Database(e) if e.is_foreign_key_violation() => {
+            ApiError::Conflict("This author still has books; delete or transfer them first.")
+        }
+        e => e.into(),
     }
 }
 
-pub async fn seed_authors(db_pool: Data<PgPool>) -> HttpResponse {
+pub(crate) async fn delete_author_record(
+    author_id: Uuid,
+    executor: impl PgExecutor<'_>,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query!("DELETE FROM authors WHERE id = $1", author_id)
+        .execute(executor)
+        .await
+        .map(|result| result.rows_affected() == 1)
+}
+
+const DEFAULT_BOOK_COUNTS_LIMIT: i64 = 50;
+const MAX_BOOK_COUNTS_LIMIT: i64 = 200;
+
+#[derive(Deserialize)]
+pub struct BookCountsParams {
+    limit: Option<i64>,
+    #[serde(default)]
+    offset: i64,
+    #[serde(default)]
+    include_empty: bool,
+}
+
+pub async fn author_book_counts(
+    params: Query<BookCountsParams>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let limit = params.limit.unwrap_or(DEFAULT_BOOK_COUNTS_LIMIT);
+    if limit < 1 {
+        return Err(ApiError::bad_request(format!(
+            "'{}' is not a valid limit.",
+            limit
+        )));
+    }
+    if params.offset < 0 {
+        return Err(ApiError::bad_request(format!(
+            "'{}' is not a valid offset.",
+            params.offset
+        )));
+    }
+
+    let rows = retrying("author_book_counts", || {
+        sqlx::query!(
+            r#"
+            SELECT authors.id, authors.name, COUNT(books.id) AS "books_count!"
+            FROM authors
+            LEFT JOIN books ON books.author_id = authors.id
+            GROUP BY authors.id
+            HAVING $3 OR COUNT(books.id) > 0
+            ORDER BY COUNT(books.id) DESC, authors.name ASC, authors.id ASC
+            LIMIT $1 OFFSET $2
+            "#,
+            limit.min(MAX_BOOK_COUNTS_LIMIT),
+            params.offset,
+            params.include_empty
+        )
+        .fetch_all(&db_pool.0)
+    })
+    .await?;
+    let counts: Vec<Value> = rows
+        .into_iter()
+        .map(|row| json!({"id": row.id, "name": row.name, "books_count": row.books_count}))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(counts))
+}
+
+pub async fn nationality_stats(db_pool: Data<ReadPool>) -> Result<HttpResponse, ApiError> {
+    let rows = retrying("nationality_stats", || {
+        sqlx::query!(
+            r#"
+            SELECT nationality, COUNT(*) AS "authors_count!"
+            FROM authors
+            GROUP BY nationality
+            ORDER BY COUNT(*) DESC, nationality ASC
+            "#
+        )
+        .fetch_all(&db_pool.0)
+    })
+    .await?;
+    let stats: Vec<Value> = rows
+        .into_iter()
+        .map(|row| json!({"nationality": row.nationality, "authors_count": row.authors_count}))
+        .collect();
+
+    Ok(HttpResponse::Ok()
+        .insert_header((header::CACHE_CONTROL, "public, max-age=300"))
+        .json(stats))
+}
+
+pub async fn seed_authors(db_pool: Data<WritePool>) -> Result<HttpResponse, ApiError> {
     let client = reqwest::Client::new();
 
     let response = client
         .get("https://gutendex.com/books/")
         .header("Content-Type", "application/json")
         .send()
-        .await
-        .expect("Failed to execute request.");
+        .await?;
 
-    let response_body = response
-        .json::<Value>()
-        .await
-        .expect("Failed to deserialize response body.");
+    let response_body = response.json::<Value>().await?;
 
     if let Some(books) = response_body["results"].as_array() {
         for book in books.iter() {
@@ -139,14 +1326,16 @@ pub async fn seed_authors(db_pool: Data<PgPool>) -> HttpResponse {
                 "Custom nationality",
                 Utc::now()
             )
-            .fetch_one(db_pool.get_ref())
+            .fetch_one(db_pool.0.named("seed_authors"))
             .await
             {
-                Ok(value) => println!("{:?}", value),
-                Err(e) => println!("{e}"),
+                Ok(record) => tracing::info!(author_id = %record.id, "Seeded author"),
+                Err(e) => {
+                    tracing::error!(author = first_author, error = %e, "Failed to seed author")
+                }
             }
         }
     }
 
-    HttpResponse::Ok().json(response_body)
+    Ok(HttpResponse::Ok().json(response_body))
 }
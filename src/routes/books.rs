@@ -1,18 +1,142 @@
 use actix_web::{
-    web::{Data, Json, Path},
-    HttpResponse,
+    http::header::{self, ContentEncoding, Encoding, HttpDate, Preference, Quality},
+    web::{Bytes, BytesMut, Data, Path, Query},
+    HttpMessage, HttpRequest, HttpResponse, HttpResponseBuilder,
 };
-use chrono::Utc;
+use async_compression::tokio::bufread::GzipEncoder;
+use chrono::{DateTime, Duration, NaiveDate, SubsecRound, Utc};
+use chrono_tz::Tz;
+use futures_util::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use sqlx::PgPool;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::SystemTime;
+use tokio_util::io::{ReaderStream, StreamReader};
 use uuid::Uuid;
 
-use crate::validations::book::NewBook;
+use crate::{
+    configuration::FeatureFlags,
+    csv::{csv_row, parse_csv},
+    db::{retrying, ReadPool, TimedPool, WritePool},
+    errors::ApiError,
+    json::Json,
+    locale::Locale,
+    ndjson::{accepts_ndjson, ndjson_line, NDJSON_CONTENT_TYPE},
+    pagination::{PageQuery, Paginated},
+    routes::{api_route, parse_uuid_path, ApiRoute, LEGACY_ROUTES_SUNSET},
+    sorting::{DefaultSorts, SortOrder, SortQuery, BOOK_SORT_COLUMNS},
+    timezone::{localize, localize_rfc3339, TimezoneQuery},
+    validations::{
+        book::{BookAuthor, BookUpdate, NewBook},
+        ValidationError,
+    },
+};
 
-pub async fn books_index(db_pool: Data<PgPool>) -> HttpResponse {
-    let rows = sqlx::query!(
-        r#"
+const BOOK_TITLE_AUTHOR_UNIQUE: &str = "books_title_author_unique";
+const BOOK_TITLE_CONFLICT: &str = "This author already has a book with that title.";
+
+pub fn routes(features: &FeatureFlags) -> Vec<ApiRoute> {
+    let mut routes = vec![api_route("GET", "/books", "List all books", books_index)
+        .route("POST", "Create a book", create_book)
+        .idempotent()];
+    if features.is_enabled("search") {
+        routes.push(api_route(
+            "GET",
+            "/books/search",
+            "Search books by title and author name",
+            search_books,
+        ));
+    } else {
+        routes.push(ApiRoute::switched_off("/books/search"));
+    }
+    if features.is_enabled("export") {
+        routes.push(api_route(
+            "GET",
+            "/books/export",
+            "Stream every book as CSV",
+            export_books,
+        ));
+    } else {
+        routes.push(ApiRoute::switched_off("/books/export"));
+    }
+    routes.push(api_route(
+        "GET",
+        "/books/random",
+        "Pick a random selection of books",
+        random_books,
+    ));
+    routes.push(api_route(
+        "GET",
+        "/books/featured",
+        "List featured books in rank order",
+        featured_books,
+    ));
+    routes.push(api_route(
+        "GET",
+        "/books/added-timeline",
+        "Count books added per day",
+        added_timeline,
+    ));
+    if features.is_enabled("legacy_routes") {
+        routes.push(
+            api_route("POST", "/books/create", "Create a book", create_book)
+                .deprecated(LEGACY_ROUTES_SUNSET, "POST /books")
+                .idempotent(),
+        );
+        routes.push(
+            api_route("POST", "/books/delete", "Delete a book", delete_book)
+                .deprecated(LEGACY_ROUTES_SUNSET, "DELETE /books/{book_id}"),
+        );
+    }
+    routes.push(api_route(
+        "POST",
+        "/books/validate",
+        "Validate a book without saving it",
+        validate_book,
+    ));
+    routes.push(api_route(
+        "POST",
+        "/books/import/preview",
+        "Validate a CSV of books without saving it",
+        preview_book_import,
+    ));
+    routes.push(
+        api_route("GET", "/books/{book_id}", "Show a book", show_book)
+            .route("HEAD", "Check when a book last changed", show_book)
+            .route("DELETE", "Delete a book", delete_book_by_id),
+    );
+    routes.push(api_route(
+        "GET",
+        "/books/{book_id}/editions",
+        "List the editions of a book's work",
+        book_editions,
+    ));
+    routes
+}
+
+pub async fn books_index(
+    req: HttpRequest,
+    query: Query<TimezoneQuery>,
+    sort_query: Query<SortQuery>,
+    page_query: Query<PageQuery>,
+    default_sorts: Data<DefaultSorts>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+    let sort = sort_query
+        .parse(&BOOK_SORT_COLUMNS, &default_sorts.books)
+        .map_err(ApiError::bad_request)?;
+    let page = page_query.parse().map_err(ApiError::bad_request)?;
+
+    if accepts_ndjson(&req) {
+        return Ok(HttpResponse::Ok()
+            .content_type(NDJSON_CONTENT_TYPE)
+            .streaming(books_ndjson_stream(db_pool.0.clone(), tz, sort)));
+    }
+
+    let rows = retrying("books_index", || {
+        sqlx::query!(
+            r#"
         SELECT
             books.id,
             books.title,
@@ -21,11 +145,28 @@ pub async fn books_index(db_pool: Data<PgPool>) -> HttpResponse {
             books.created_at
         FROM books
         JOIN authors ON books.author_id = authors.id
-        "#
-    )
-    .fetch_all(db_pool.get_ref())
-    .await
-    .expect("Failed to fetch saved books.");
+        ORDER BY
+            CASE WHEN $1 = 'title' THEN books.title END ASC,
+            CASE WHEN $1 = '-title' THEN books.title END DESC,
+            CASE WHEN $1 = 'genre' THEN books.genre END ASC,
+            CASE WHEN $1 = '-genre' THEN books.genre END DESC,
+            CASE WHEN $1 = '-created_at' THEN books.created_at END DESC,
+            books.created_at ASC,
+            books.id ASC
+        LIMIT $2 OFFSET $3
+        "#,
+            sort.as_ref(),
+            page.per_page,
+            page.offset()
+        )
+        .fetch_all(&db_pool.0)
+    })
+    .await?;
+    let total = retrying("books_index.total", || {
+        sqlx::query!(r#"SELECT COUNT(*) AS "total!" FROM books"#).fetch_one(&db_pool.0)
+    })
+    .await?
+    .total;
 
     let books: Vec<serde_json::Value> = rows
         .into_iter()
@@ -35,18 +176,175 @@ pub async fn books_index(db_pool: Data<PgPool>) -> HttpResponse {
                 "title": row.title,
                 "author": row.authors_name,
                 "genre": row.genre,
-                "created_at": row.created_at
+                "created_at": localize(row.created_at, tz)
             })
         })
         .collect();
 
-    HttpResponse::Ok().json(books)
+    Ok(HttpResponse::Ok()
+        .page_headers(&req, page, total)
+        .json(books))
 }
 
-pub async fn show_book(info: Path<String>, db_pool: Data<PgPool>) -> HttpResponse {
-    let book_id = info.into_inner();
-    match sqlx::query!(
-        r#"
+fn books_ndjson_stream(
+    db_pool: TimedPool,
+    tz: Option<Tz>,
+    sort: SortOrder,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> {
+    async_stream::stream! {
+        let mut rows = sqlx::query!(
+            r#"
+            SELECT
+                books.id,
+                books.title,
+                authors.name AS "authors_name",
+                books.genre,
+                books.created_at
+            FROM books
+            JOIN authors ON books.author_id = authors.id
+            ORDER BY
+                CASE WHEN $1 = 'title' THEN books.title END ASC,
+                CASE WHEN $1 = '-title' THEN books.title END DESC,
+                CASE WHEN $1 = 'genre' THEN books.genre END ASC,
+                CASE WHEN $1 = '-genre' THEN books.genre END DESC,
+                CASE WHEN $1 = '-created_at' THEN books.created_at END DESC,
+                books.created_at ASC,
+                books.id ASC
+            "#,
+            sort.as_ref()
+        )
+        .fetch(db_pool.named("books_ndjson"));
+
+        while let Some(row) = rows.next().await {
+            match row {
+                Ok(book) => {
+                    yield Ok(ndjson_line(&json!({
+                        "id": book.id,
+                        "title": book.title,
+                        "author": book.authors_name,
+                        "genre": book.genre,
+                        "created_at": localize(book.created_at, tz)
+                    })))
+                }
+                Err(e) => {
+                    yield Err(std::io::Error::other(e));
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct BookSearchParams {
+    q: String,
+}
+
+// Title matches weigh more than author name matches, so "Tolkien" ranks
+// books titled after him above the ones he wrote.
+pub async fn search_books(
+    req: HttpRequest,
+    params: Query<BookSearchParams>,
+    page_query: Query<PageQuery>,
+    query: Query<TimezoneQuery>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+    let term = params.q.trim();
+    if term.is_empty() {
+        return Err(ApiError::bad_request("Search query must not be empty."));
+    }
+    let page = page_query.parse().map_err(ApiError::bad_request)?;
+
+    let rows = retrying("search_books", || {
+        sqlx::query!(
+            r#"
+            SELECT
+                books.id,
+                books.title,
+                authors.name AS "authors_name",
+                books.genre,
+                books.created_at,
+                ts_rank(
+                    setweight(to_tsvector('english', books.title), 'A')
+                        || setweight(to_tsvector('english', authors.name), 'B'),
+                    query
+                ) AS "rank!"
+            FROM books
+            JOIN authors ON books.author_id = authors.id,
+                websearch_to_tsquery('english', $1) AS query
+            WHERE to_tsvector('english', books.title) @@ query
+                OR to_tsvector('english', authors.name) @@ query
+            ORDER BY 6 DESC, books.title ASC, books.id ASC
+            LIMIT $2 OFFSET $3
+            "#,
+            term,
+            page.per_page,
+            page.offset()
+        )
+        .fetch_all(&db_pool.0)
+    })
+    .await?;
+    let total = retrying("search_books.total", || {
+        sqlx::query!(
+            r#"
+            SELECT COUNT(*) AS "total!"
+            FROM books
+            JOIN authors ON books.author_id = authors.id,
+                websearch_to_tsquery('english', $1) AS query
+            WHERE to_tsvector('english', books.title) @@ query
+                OR to_tsvector('english', authors.name) @@ query
+            "#,
+            term
+        )
+        .fetch_one(&db_pool.0)
+    })
+    .await?
+    .total;
+
+    let books: Vec<Value> = rows
+        .into_iter()
+        .map(|row| {
+            json!({
+                "id": row.id,
+                "title": row.title,
+                "author": row.authors_name,
+                "genre": row.genre,
+                "created_at": localize(row.created_at, tz),
+                "rank": row.rank
+            })
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok()
+        .page_headers(&req, page, total)
+        .json(books))
+}
+
+const MAX_RANDOM_BOOKS: i64 = 20;
+
+#[derive(Deserialize)]
+pub struct RandomBooksParams {
+    count: Option<i64>,
+}
+
+pub async fn random_books(
+    params: Query<RandomBooksParams>,
+    query: Query<TimezoneQuery>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+    let count = params.count.unwrap_or(5);
+    if count < 1 {
+        return Err(ApiError::bad_request(format!(
+            "'{}' is not a valid count.",
+            count
+        )));
+    }
+
+    let rows = retrying("random_books", || {
+        sqlx::query!(
+            r#"
         SELECT
             books.id,
             books.title,
@@ -55,69 +353,630 @@ pub async fn show_book(info: Path<String>, db_pool: Data<PgPool>) -> HttpRespons
             books.created_at
         FROM books
         JOIN authors ON books.author_id = authors.id
+        ORDER BY RANDOM()
+        LIMIT $1
+        "#,
+            count.min(MAX_RANDOM_BOOKS)
+        )
+        .fetch_all(&db_pool.0)
+    })
+    .await?;
+    let books: Vec<serde_json::Value> = rows
+        .into_iter()
+        .map(|row| {
+            json!({
+                "id": row.id,
+                "title": row.title,
+                "author": row.authors_name,
+                "genre": row.genre,
+                "created_at": localize(row.created_at, tz)
+            })
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(books))
+}
+
+pub async fn featured_books(
+    query: Query<TimezoneQuery>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+    let rows = retrying("featured_books", || {
+        sqlx::query!(
+            r#"
+        SELECT
+            books.id,
+            books.title,
+            authors.name AS "authors_name",
+            books.genre,
+            books.created_at,
+            books.featured_rank AS "featured_rank!"
+        FROM books
+        JOIN authors ON books.author_id = authors.id
+        WHERE books.featured_rank IS NOT NULL
+        ORDER BY books.featured_rank
+        "#
+        )
+        .fetch_all(&db_pool.0)
+    })
+    .await?;
+    let books: Vec<serde_json::Value> = rows
+        .into_iter()
+        .map(|row| {
+            json!({
+                "id": row.id,
+                "title": row.title,
+                "author": row.authors_name,
+                "genre": row.genre,
+                "created_at": localize(row.created_at, tz),
+                "featured_rank": row.featured_rank
+            })
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(books))
+}
+
+const MAX_TIMELINE_DAYS: i64 = 366;
+const DEFAULT_TIMELINE_DAYS: i64 = 30;
+
+#[derive(Deserialize)]
+pub struct TimelineParams {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    #[serde(default)]
+    fill_gaps: bool,
+}
+
+// Days are UTC calendar days and both ends of the range are inclusive.
+pub async fn added_timeline(
+    params: Query<TimelineParams>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let to = params.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = params
+        .from
+        .unwrap_or(to - Duration::days(DEFAULT_TIMELINE_DAYS - 1));
+    if from > to {
+        return Err(ApiError::bad_request("'from' must not be after 'to'."));
+    }
+    if (to - from).num_days() + 1 > MAX_TIMELINE_DAYS {
+        return Err(ApiError::bad_request(format!(
+            "The timeline can span at most {} days.",
+            MAX_TIMELINE_DAYS
+        )));
+    }
+
+    let rows = retrying("added_timeline", || {
+        sqlx::query!(
+            r#"SELECT
+                date_trunc('day', created_at AT TIME ZONE 'UTC')::date AS "day!",
+                COUNT(*) AS "books_count!"
+            FROM books
+            WHERE created_at >= $1::date AT TIME ZONE 'UTC'
+                AND created_at < ($2::date + 1) AT TIME ZONE 'UTC'
+            GROUP BY 1
+            ORDER BY 1"#,
+            from,
+            to
+        )
+        .fetch_all(&db_pool.0)
+    })
+    .await?;
+
+    let timeline: Vec<serde_json::Value> = if params.fill_gaps {
+        let counts: HashMap<NaiveDate, i64> = rows
+            .into_iter()
+            .map(|row| (row.day, row.books_count))
+            .collect();
+        from.iter_days()
+            .take_while(|day| *day <= to)
+            .map(|day| json!({"day": day, "books_count": counts.get(&day).copied().unwrap_or(0)}))
+            .collect()
+    } else {
+        rows.into_iter()
+            .map(|row| json!({"day": row.day, "books_count": row.books_count}))
+            .collect()
+    };
+
+    Ok(HttpResponse::Ok().json(timeline))
+}
+
+pub async fn show_book(
+    req: HttpRequest,
+    info: Path<String>,
+    query: Query<TimezoneQuery>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let book_id = parse_uuid_path(&info)?;
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+
+    let book = retrying("show_book", || {
+        sqlx::query!(
+            r#"
+        SELECT
+            books.id,
+            books.title,
+            authors.name AS "authors_name",
+            books.genre,
+            books.parent_book_id,
+            books.created_at,
+            books.updated_at
+        FROM books
+        JOIN authors ON books.author_id = authors.id
         WHERE books.id = $1
         "#,
-        Uuid::parse_str(&book_id).unwrap_or_default(),
-    )
-    .fetch_one(db_pool.get_ref())
-    .await
-    {
-        Ok(book) => {
-            let book_json = json!({
-                "id": book.id,
-                "title": book.title,
-                "author": book.authors_name,
-                "genre": book.genre,
-                "created_at": book.created_at
-            });
+            book_id,
+        )
+        .fetch_optional(&db_pool.0)
+    })
+    .await?
+    .ok_or(ApiError::NotFound("Book not found"))?;
 
-            HttpResponse::Ok().json(book_json)
-        }
-        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    // HTTP dates only carry whole seconds.
+    let last_modified = HttpDate::from(SystemTime::from(book.updated_at.trunc_subsecs(0)));
+    let not_modified = req
+        .get_header::<header::IfModifiedSince>()
+        .is_some_and(|since| SystemTime::from(last_modified) <= SystemTime::from(since.0));
+    if not_modified {
+        return Ok(HttpResponse::NotModified()
+            .insert_header(header::LastModified(last_modified))
+            .finish());
     }
+
+    let book_json = json!({
+        "id": book.id,
+        "title": book.title,
+        "author": book.authors_name,
+        "genre": book.genre,
+        "parent_book_id": book.parent_book_id,
+        "created_at": localize(book.created_at, tz)
+    });
+
+    Ok(HttpResponse::Ok()
+        .insert_header(header::LastModified(last_modified))
+        .json(book_json))
+}
+
+pub async fn book_editions(
+    info: Path<String>,
+    query: Query<TimezoneQuery>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let book_id = parse_uuid_path(&info)?;
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+
+    // Asking from an edition lists its siblings: the work is its parent.
+    let work_id = retrying("book_editions.work", || {
+        sqlx::query!(
+            r#"SELECT COALESCE(parent_book_id, id) AS "work_id!" FROM books WHERE id = $1"#,
+            book_id
+        )
+        .fetch_optional(&db_pool.0)
+    })
+    .await?
+    .ok_or(ApiError::NotFound("Book not found"))?
+    .work_id;
+
+    let rows = retrying("book_editions", || {
+        sqlx::query!(
+            r#"
+            SELECT
+                books.id,
+                books.title,
+                authors.name AS "authors_name",
+                books.genre,
+                books.created_at
+            FROM books
+            JOIN authors ON books.author_id = authors.id
+            WHERE books.parent_book_id = $1
+            ORDER BY books.created_at ASC, books.id ASC
+            "#,
+            work_id
+        )
+        .fetch_all(&db_pool.0)
+    })
+    .await?;
+    let editions: Vec<Value> = rows
+        .into_iter()
+        .map(|row| {
+            json!({
+                "id": row.id,
+                "title": row.title,
+                "author": row.authors_name,
+                "genre": row.genre,
+                "created_at": localize(row.created_at, tz)
+            })
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(json!({"work_id": work_id, "editions": editions})))
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct NewBookData {
     pub title: String,
+    #[serde(default)]
     pub author: String,
+    #[serde(default)]
+    pub author_id: Option<Uuid>,
     pub genre: String,
+    #[serde(default)]
+    pub parent_book_id: Option<Uuid>,
 }
 
-pub async fn create_book(input: Json<NewBookData>, db_pool: Data<PgPool>) -> HttpResponse {
-    let new_book: NewBook = match input.0.try_into() {
-        Ok(value) => value,
-        Err(e) => return HttpResponse::BadRequest().body(e),
-    };
+pub async fn create_book(
+    req: HttpRequest,
+    input: Json<NewBookData>,
+    query: Query<TimezoneQuery>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+    let (new_book, author_id) = check_book(input.into_inner(), &db_pool.0)
+        .await?
+        .map_err(|errors| ApiError::invalid(&req, errors))?;
+
+    match insert_book(&new_book, author_id, &db_pool.0).await {
+        Ok(book) => {
+            let mut body = json!(book);
+            body["created_at"] = localize(book.created_at, tz);
+            Ok(HttpResponse::Created()
+                .insert_header((header::LOCATION, format!("/books/{}", book.id)))
+                .json(body))
+        }
+        Err(e) if is_title_conflict(&e) => book_conflict(&new_book, author_id, &db_pool.0).await,
+        Err(e) => Err(e.into()),
+    }
+}
 
-    let author = match sqlx::query!(
-        "SELECT id, name, nationality, created_at FROM authors WHERE name = $1",
-        new_book.author.as_ref()
+/// Whether `error` is an author ending up with two original works of the
+/// same title, which any write moving a title or an author can run into.
+pub(crate) fn is_title_conflict(error: &sqlx::Error) -> bool {
+    matches!(error, sqlx::Error::Database(e) if e.constraint() == Some(BOOK_TITLE_AUTHOR_UNIQUE))
+}
+
+/// The error for a title conflict where there is no room for the existing
+/// book's id, as in GraphQL.
+pub(crate) fn title_conflict_error(error: sqlx::Error) -> ApiError {
+    if is_title_conflict(&error) {
+        ApiError::Conflict(BOOK_TITLE_CONFLICT)
+    } else {
+        error.into()
+    }
+}
+
+// Unlike the other errors, a duplicate points the client at the book that
+// already exists, so it keeps its own body and `Location` header. That book
+// may have been deleted or renamed since the insert failed, in which case
+// there is nothing to point at.
+async fn book_conflict(
+    new_book: &NewBook,
+    author_id: Uuid,
+    db_pool: &TimedPool,
+) -> Result<HttpResponse, ApiError> {
+    let existing = sqlx::query!(
+        "SELECT id FROM books
+        WHERE lower(title) = lower($1) AND author_id = $2 AND parent_book_id IS NULL",
+        new_book.title.as_ref(),
+        author_id
     )
-    .fetch_one(db_pool.get_ref())
-    .await
-    {
-        Ok(author) => author,
-        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    .fetch_optional(db_pool.named("create_book.find_existing"))
+    .await?;
+    let Some(existing) = existing else {
+        return Err(ApiError::Conflict(BOOK_TITLE_CONFLICT));
     };
 
-    match sqlx::query!(
-        "INSERT INTO books (title, genre, author_id, created_at)
-        VALUES ($1, $2, $3, $4)
-        RETURNING id",
+    Ok(HttpResponse::Conflict()
+        .insert_header((header::LOCATION, format!("/books/{}", existing.id)))
+        .json(json!({
+            "message": BOOK_TITLE_CONFLICT,
+            "book_id": existing.id
+        })))
+}
+
+/// A book as just written, in the same shape as `GET /books/{book_id}`
+/// plus the author's id.
+#[derive(Serialize)]
+pub struct CreatedBook {
+    pub id: Uuid,
+    pub title: String,
+    pub author: String,
+    pub author_id: Uuid,
+    pub genre: String,
+    pub parent_book_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+pub(crate) async fn insert_book(
+    new_book: &NewBook,
+    author_id: Uuid,
+    db_pool: &TimedPool,
+) -> Result<CreatedBook, sqlx::Error> {
+    sqlx::query_as!(
+        CreatedBook,
+        r#"
+    WITH inserted AS (
+        INSERT INTO books (title, genre, author_id, parent_book_id, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $5)
+        RETURNING id, title, genre, author_id, parent_book_id, created_at
+    )
+    SELECT
+        inserted.id,
+        inserted.title,
+        authors.name AS author,
+        inserted.author_id,
+        inserted.genre,
+        inserted.parent_book_id,
+        inserted.created_at
+    FROM inserted
+    JOIN authors ON inserted.author_id = authors.id
+    "#,
         new_book.title.as_ref(),
         new_book.genre.as_ref(),
-        author.id,
+        author_id,
+        new_book.parent_book_id,
         Utc::now()
     )
-    .fetch_one(db_pool.get_ref())
+    .fetch_one(db_pool.named("create_book.insert"))
     .await
-    {
-        Ok(record) => HttpResponse::Ok().json(json!({
-            "message": "Book created successfully!",
-            "book_id": record.id
-        })),
-        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+}
+
+/// What GraphQL's `updateBook` may change.
+pub struct UpdateBookData {
+    pub title: Option<String>,
+    pub genre: Option<String>,
+}
+
+/// Applies `update` and answers the book as it now is, or `None` when
+/// there is no such book. Moving a title onto one of the author's other
+/// works is a title conflict.
+pub(crate) async fn update_book_record(
+    book_id: Uuid,
+    update: &BookUpdate,
+    db_pool: &TimedPool,
+) -> Result<Option<CreatedBook>, sqlx::Error> {
+    sqlx::query_as!(
+        CreatedBook,
+        r#"
+    WITH updated AS (
+        UPDATE books
+        SET title = COALESCE($2, title), genre = COALESCE($3, genre)
+        WHERE id = $1
+        RETURNING id, title, genre, author_id, parent_book_id, created_at
+    )
+    SELECT
+        updated.id,
+        updated.title,
+        authors.name AS author,
+        updated.author_id,
+        updated.genre,
+        updated.parent_book_id,
+        updated.created_at
+    FROM updated
+    JOIN authors ON updated.author_id = authors.id
+    "#,
+        book_id,
+        update.title.as_ref().map(AsRef::as_ref),
+        update.genre.as_ref().map(AsRef::as_ref)
+    )
+    .fetch_optional(db_pool.named("update_book"))
+    .await
+}
+
+pub async fn validate_book(
+    req: HttpRequest,
+    input: Json<NewBookData>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let locale = Locale::negotiate(&req);
+    match check_book(input.into_inner(), &db_pool.0).await? {
+        Ok(_) => Ok(HttpResponse::Ok().json(json!({"valid": true}))),
+        Err(errors) => {
+            let errors: Vec<Value> = errors.iter().map(|error| error.to_json(locale)).collect();
+            Ok(HttpResponse::BadRequest()
+                .insert_header((header::CONTENT_LANGUAGE, locale.tag()))
+                .json(json!({"valid": false, "errors": errors})))
+        }
+    }
+}
+
+const MAX_PREVIEW_ROWS: usize = 1_000;
+const IMPORT_COLUMNS: [&str; 3] = ["title", "author", "genre"];
+
+// Expects a header row naming at least title, author and genre, in any
+// order; other columns such as the export's id are ignored.
+pub async fn preview_book_import(
+    req: HttpRequest,
+    body: String,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let locale = Locale::negotiate(&req);
+    let mut records = parse_csv(&body).map_err(ApiError::bad_request)?.into_iter();
+    let header = records.next().unwrap_or_default();
+    let mut positions = Vec::with_capacity(IMPORT_COLUMNS.len());
+    for column in IMPORT_COLUMNS {
+        match header.iter().position(|name| name.trim() == column) {
+            Some(position) => positions.push(position),
+            None => {
+                return Err(ApiError::bad_request(format!(
+                    "Missing '{}' column.",
+                    column
+                )))
+            }
+        }
+    }
+
+    let records: Vec<Vec<String>> = records.collect();
+    let total = records.len();
+    let mut lengths = Vec::new();
+    let mut inputs = Vec::new();
+    for mut record in records.into_iter().take(MAX_PREVIEW_ROWS) {
+        lengths.push(record.len());
+        if record.len() == header.len() {
+            let mut field = |position: usize| std::mem::take(&mut record[position]);
+            inputs.push(NewBookData {
+                title: field(positions[0]),
+                author: field(positions[1]),
+                author_id: None,
+                genre: field(positions[2]),
+                parent_book_id: None,
+            });
+        }
+    }
+    let mut checked = check_books(inputs, &db_pool.0).await?.into_iter();
+
+    let mut rows = Vec::new();
+    let mut valid = 0;
+    for (index, length) in lengths.into_iter().enumerate() {
+        let errors = if length != header.len() {
+            vec![ValidationError::new("malformed_row", length.to_string())]
+        } else {
+            checked.next().and_then(Result::err).unwrap_or_default()
+        };
+
+        if errors.is_empty() {
+            valid += 1;
+        }
+        rows.push(json!({
+            "row": index + 1,
+            "valid": errors.is_empty(),
+            "errors": errors.iter().map(|error| error.to_json(locale)).collect::<Vec<Value>>()
+        }));
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header((header::CONTENT_LANGUAGE, locale.tag()))
+        .json(json!({
+            "summary": {
+                "rows": total,
+                "previewed": rows.len(),
+                "valid": valid,
+                "invalid": rows.len() - valid
+            },
+            "rows": rows
+        })))
+}
+
+// Shared by create_book, validate_book and preview_book_import so they
+// can't drift apart.
+pub(crate) async fn check_book(
+    input: NewBookData,
+    db_pool: &TimedPool,
+) -> Result<Result<(NewBook, Uuid), Vec<ValidationError>>, sqlx::Error> {
+    let mut checked = check_books(vec![input], db_pool).await?;
+    Ok(checked.remove(0))
+}
+
+// Looks up every author and parent book the inputs refer to with one query
+// per table, so checking a whole import doesn't cost a round trip per row.
+pub(crate) async fn check_books(
+    inputs: Vec<NewBookData>,
+    db_pool: &TimedPool,
+) -> Result<Vec<Result<(NewBook, Uuid), Vec<ValidationError>>>, sqlx::Error> {
+    let parsed: Vec<_> = inputs
+        .into_iter()
+        .map(|input| {
+            let author_ref = match input.author_id {
+                Some(author_id) => ("author_id", author_id.to_string()),
+                None => ("author", input.author.clone()),
+            };
+            (author_ref, input.parent_book_id, NewBook::try_from(input))
+        })
+        .collect();
+
+    let mut author_names = Vec::new();
+    let mut author_ids = Vec::new();
+    for (_, _, new_book) in &parsed {
+        match new_book.as_ref().map(|new_book| &new_book.author) {
+            Ok(BookAuthor::Name(name)) => author_names.push(name.as_ref().to_owned()),
+            Ok(BookAuthor::Id(author_id)) => author_ids.push(*author_id),
+            Err(_) => {}
+        }
+    }
+    let parent_ids: Vec<Uuid> = parsed
+        .iter()
+        .filter_map(|(_, parent_id, _)| *parent_id)
+        .collect();
+
+    let authors = match (author_names.is_empty(), author_ids.is_empty()) {
+        (true, true) => Vec::new(),
+        _ => {
+            sqlx::query!(
+                "SELECT id, name FROM authors WHERE name = ANY($1) OR id = ANY($2)",
+                &author_names,
+                &author_ids
+            )
+            .fetch_all(db_pool.named("check_books.find_authors"))
+            .await?
+        }
+    };
+    let ids_by_name: HashMap<&str, Uuid> = authors
+        .iter()
+        .map(|author| (author.name.as_str(), author.id))
+        .collect();
+    let parents: HashMap<Uuid, Option<Uuid>> = match parent_ids.is_empty() {
+        true => HashMap::new(),
+        false => sqlx::query!(
+            "SELECT id, parent_book_id FROM books WHERE id = ANY($1)",
+            &parent_ids
+        )
+        .fetch_all(db_pool.named("check_books.find_parents"))
+        .await?
+        .into_iter()
+        .map(|parent| (parent.id, parent.parent_book_id))
+        .collect(),
+    };
+
+    Ok(parsed
+        .into_iter()
+        .map(|((author_field, author_ref), parent_id, new_book)| {
+            let author_id = match new_book.as_ref().map(|new_book| &new_book.author) {
+                Ok(BookAuthor::Name(name)) => ids_by_name.get(name.as_ref()).copied(),
+                Ok(BookAuthor::Id(author_id)) => authors
+                    .iter()
+                    .any(|author| author.id == *author_id)
+                    .then_some(*author_id),
+                Err(_) => None,
+            };
+            let parent_error = parent_id
+                .and_then(|parent_id| check_parent_book(parent_id, &parents))
+                .map(|e| e.on("parent_book_id"));
+
+            let mut checked = match (new_book, author_id) {
+                (Ok(new_book), Some(author_id)) => Ok((new_book, author_id)),
+                (Ok(_), None) => Err(vec![
+                    ValidationError::new("author_not_found", author_ref).on(author_field)
+                ]),
+                (Err(errors), _) => Err(errors),
+            };
+            if let Some(error) = parent_error {
+                match &mut checked {
+                    Ok(_) => checked = Err(vec![error]),
+                    Err(errors) => errors.push(error),
+                }
+            }
+            checked
+        })
+        .collect())
+}
+
+// Editions hang directly off the original work, never off another edition.
+// `parents` maps each book found to its own parent, if it has one.
+fn check_parent_book(
+    parent_id: Uuid,
+    parents: &HashMap<Uuid, Option<Uuid>>,
+) -> Option<ValidationError> {
+    match parents.get(&parent_id) {
+        None => Some(ValidationError::new(
+            "parent_book_not_found",
+            parent_id.to_string(),
+        )),
+        Some(Some(_)) => Some(ValidationError::new(
+            "parent_book_is_edition",
+            parent_id.to_string(),
+        )),
+        Some(None) => None,
     }
 }
 
@@ -126,20 +985,179 @@ pub struct BookId {
     id: String,
 }
 
-pub async fn delete_book(input: Json<BookId>, db_pool: Data<PgPool>) -> HttpResponse {
-    match sqlx::query!(
-        "DELETE FROM books WHERE id = $1",
-        Uuid::parse_str(&input.id).unwrap_or_default(),
-    )
-    .execute(db_pool.get_ref())
-    .await
-    {
-        Ok(result) => match result.rows_affected() == 1 {
-            true => HttpResponse::Ok().json(json!({"message": "Book deleted successfully!"})),
-            false => {
-                HttpResponse::NotFound().json(json!({"message": "Book to be deleted not found"}))
+pub async fn delete_book(
+    input: Json<BookId>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    remove_book(parse_uuid_path(&input.id)?, &db_pool).await
+}
+
+pub async fn delete_book_by_id(
+    input: Path<String>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    remove_book(parse_uuid_path(&input)?, &db_pool).await
+}
+
+async fn remove_book(book_id: Uuid, db_pool: &WritePool) -> Result<HttpResponse, ApiError> {
+    let deleted = delete_book_record(book_id, &db_pool.0)
+        .await
+        .map_err(delete_book_error)?;
+    if !deleted {
+        return Err(ApiError::NotFound("Book to be deleted not found"));
+    }
+    Ok(HttpResponse::Ok().json(json!({"message": "Book deleted successfully!"})))
+}
+
+/// The error for a book that can't go: a work still has editions pointing
+/// at it, so the client has to delete or reassign those first.
+pub(crate) fn delete_book_error(error: sqlx::Error) -> ApiError {
+    match error {
+        sqlx::Error::Database(e) if e.is_foreign_key_violation() => ApiError::Conflict(
+            "This book still has editions; delete them or reassign them to another work first.",
+        ),
+        e => title_conflict_error(e),
+    }
+}
+
+pub(crate) async fn delete_book_record(
+    book_id: Uuid,
+    db_pool: &TimedPool,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query!("DELETE FROM books WHERE id = $1", book_id)
+        .execute(db_pool.named("delete_book"))
+        .await
+        .map(|result| result.rows_affected() == 1)
+}
+
+pub async fn export_books(
+    req: HttpRequest,
+    query: Query<TimezoneQuery>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+    let csv_stream = books_csv_stream(db_pool.0.clone(), tz);
+
+    // There is no validator to check an `If-Range` against, so the catalog
+    // may have changed since the first part and the whole file is sent.
+    if !req.headers().contains_key(header::IF_RANGE) {
+        if let Some(header::Range::Bytes(ranges)) = req.get_header::<header::Range>() {
+            if let [range] = ranges.as_slice() {
+                return export_books_range(range, csv_stream).await;
             }
-        },
-        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        }
+    }
+
+    // `gzip;q=0` refuses gzip, so the quality matters and not just the name.
+    let accepts_gzip = req
+        .get_header::<header::AcceptEncoding>()
+        .is_some_and(|accepted| {
+            accepted.iter().any(|encoding| {
+                encoding.item == Preference::Specific(Encoding::gzip())
+                    && encoding.quality > Quality::ZERO
+            })
+        });
+
+    let mut response = books_csv_response();
+    if accepts_gzip {
+        let encoder = GzipEncoder::new(StreamReader::new(csv_stream));
+        Ok(response
+            .insert_header(ContentEncoding::Gzip)
+            .streaming(ReaderStream::new(encoder)))
+    } else {
+        Ok(response.streaming(csv_stream))
+    }
+}
+
+fn books_csv_response() -> HttpResponseBuilder {
+    let mut response = HttpResponse::Ok();
+    response
+        .content_type("text/csv; charset=utf-8")
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"books.csv\"",
+        ));
+    response
+}
+
+// Range requests render the export in memory to cut a slice out of it, so
+// past this size they get the whole file instead.
+const MAX_RANGE_EXPORT_BYTES: usize = 8 * 1024 * 1024;
+
+// The CSV is generated on the fly, so a byte range can only be cut out of a
+// fully rendered export, and resuming is only consistent while the catalog
+// is not modified in between. Ranges are therefore served, but not
+// advertised with `Accept-Ranges`.
+async fn export_books_range(
+    range: &header::ByteRangeSpec,
+    csv_stream: impl Stream<Item = Result<Bytes, std::io::Error>> + 'static,
+) -> Result<HttpResponse, ApiError> {
+    let mut csv_stream = Box::pin(csv_stream);
+    let mut csv = BytesMut::new();
+    while let Some(chunk) = csv_stream.next().await {
+        csv.extend_from_slice(&chunk.map_err(sqlx::Error::Io)?);
+        if csv.len() > MAX_RANGE_EXPORT_BYTES {
+            let rendered = futures_util::stream::once(async move { Ok(csv.freeze()) });
+            return Ok(books_csv_response().streaming(rendered.chain(csv_stream)));
+        }
+    }
+    let length = csv.len() as u64;
+
+    Ok(match range.to_satisfiable_range(length) {
+        Some((start, end)) => HttpResponse::PartialContent()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header(header::ContentRange(header::ContentRangeSpec::Bytes {
+                range: Some((start, end)),
+                instance_length: Some(length),
+            }))
+            .body(csv.freeze().slice(start as usize..=end as usize)),
+        None => HttpResponse::RangeNotSatisfiable()
+            .insert_header(header::ContentRange(header::ContentRangeSpec::Bytes {
+                range: None,
+                instance_length: Some(length),
+            }))
+            .finish(),
+    })
+}
+
+fn books_csv_stream(
+    db_pool: TimedPool,
+    tz: Option<Tz>,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> {
+    async_stream::stream! {
+        yield Ok(Bytes::from(csv_row(&["id", "title", "author", "genre", "created_at"])));
+
+        let mut rows = sqlx::query!(
+            r#"
+            SELECT
+                books.id,
+                books.title,
+                authors.name AS "authors_name",
+                books.genre,
+                books.created_at
+            FROM books
+            JOIN authors ON books.author_id = authors.id
+            ORDER BY books.created_at ASC, books.id ASC
+            "#
+        )
+        .fetch(db_pool.named("export_books"));
+
+        while let Some(row) = rows.next().await {
+            match row {
+                Ok(book) => {
+                    yield Ok(Bytes::from(csv_row(&[
+                        &book.id.to_string(),
+                        &book.title,
+                        &book.authors_name,
+                        &book.genre,
+                        &localize_rfc3339(book.created_at, tz),
+                    ])))
+                }
+                Err(e) => {
+                    yield Err(std::io::Error::other(e));
+                    break;
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,62 @@
+use crate::{
+    graphql::LibrarySchema,
+    middleware::{maintenance_response, MaintenanceMode},
+    routes::{api_route, ApiRoute},
+};
+use actix_web::{web::Data, Either, HttpRequest, HttpResponse};
+use async_graphql::{
+    http::GraphiQLSource,
+    parser::{parse_query, types::OperationType},
+};
+use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
+
+pub fn routes() -> Vec<ApiRoute> {
+    vec![
+        api_route(
+            "POST",
+            "/graphql",
+            "Query authors, books and users with GraphQL",
+            graphql,
+        ),
+        api_route(
+            "GET",
+            "/graphiql",
+            "Explore the GraphQL schema in the browser",
+            graphiql,
+        ),
+    ]
+}
+
+pub async fn graphql(
+    req: HttpRequest,
+    schema: Data<LibrarySchema>,
+    request: GraphQLRequest,
+) -> Either<GraphQLResponse, HttpResponse> {
+    let request = request.into_inner();
+    // Maintenance mode lets every POST to `/graphql` through, so only the
+    // mutations are turned away here and queries keep working.
+    let in_maintenance = req
+        .app_data::<Data<MaintenanceMode>>()
+        .is_some_and(|mode| mode.is_enabled());
+    if in_maintenance && has_mutation(&request.query) {
+        return Either::Right(maintenance_response());
+    }
+
+    Either::Left(schema.execute(request).await.into())
+}
+
+// A document that doesn't parse is left for the schema to report.
+fn has_mutation(query: &str) -> bool {
+    parse_query(query).is_ok_and(|document| {
+        document
+            .operations
+            .iter()
+            .any(|(_, operation)| operation.node.ty == OperationType::Mutation)
+    })
+}
+
+pub async fn graphiql() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(GraphiQLSource::build().endpoint("/graphql").finish())
+}
@@ -1,5 +1,15 @@
+use crate::routes::{api_route, ApiRoute};
 use actix_web::HttpResponse;
 
+pub fn routes() -> Vec<ApiRoute> {
+    vec![api_route(
+        "GET",
+        "/health_check",
+        "Check that the service is up",
+        health_check,
+    )]
+}
+
 pub async fn health_check() -> HttpResponse {
     HttpResponse::Ok().finish()
 }
@@ -1,9 +1,191 @@
+pub mod admin;
 pub mod authors;
 pub mod books;
+pub mod graphql;
 pub mod health_check;
 pub mod users;
 
-pub use authors::*;
-pub use books::*;
-pub use health_check::*;
-pub use users::*;
+use crate::{configuration::FeatureFlags, errors::ApiError, responses::error_envelope};
+use actix_web::{
+    http::{header, Method},
+    web::{self, Data, ServiceConfig},
+    FromRequest, Handler, HttpResponse, Resource, Responder, ResponseError, Route,
+};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Clone, Serialize)]
+pub struct Endpoint {
+    pub method: &'static str,
+    pub path: &'static str,
+    pub description: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecation: Option<Deprecation>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub idempotent: bool,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Deprecation {
+    pub sunset: &'static str,
+    pub successor: &'static str,
+}
+
+/// When the `/create` and `/delete` POST routes, superseded by verbs on the
+/// resource itself, stop being served. Until then the `legacy_routes`
+/// feature flag can already switch them off.
+pub const LEGACY_ROUTES_SUNSET: &str = "Thu, 01 Apr 2027 00:00:00 GMT";
+
+pub struct ApiRoute {
+    pub path: &'static str,
+    pub endpoints: Vec<Endpoint>,
+    pub resource: Resource,
+}
+
+pub fn api_route<F, Args>(
+    method: &'static str,
+    path: &'static str,
+    description: &'static str,
+    handler: F,
+) -> ApiRoute
+where
+    F: Handler<Args>,
+    Args: FromRequest + 'static,
+    F::Output: Responder + 'static,
+{
+    ApiRoute {
+        path,
+        endpoints: Vec::new(),
+        resource: web::resource(path),
+    }
+    .route(method, description, handler)
+}
+
+impl ApiRoute {
+    /// Serves another method on the same path.
+    pub fn route<F, Args>(
+        mut self,
+        method: &'static str,
+        description: &'static str,
+        handler: F,
+    ) -> Self
+    where
+        F: Handler<Args>,
+        Args: FromRequest + 'static,
+        F::Output: Responder + 'static,
+    {
+        let route_method = Method::from_bytes(method.as_bytes()).expect("Invalid route method.");
+        self.endpoints.push(Endpoint {
+            method,
+            path: self.path,
+            description,
+            deprecation: None,
+            idempotent: false,
+        });
+        let allowed = self
+            .endpoints
+            .iter()
+            .map(|endpoint| endpoint.method)
+            .collect::<Vec<&str>>()
+            .join(", ");
+        self.resource = self
+            .resource
+            .route(web::method(route_method).to(handler))
+            .default_service(method_not_allowed(allowed));
+        self
+    }
+
+    /// Marks the most recently added method as deprecated; the
+    /// `deprecation_headers` middleware announces it on every response.
+    pub fn deprecated(mut self, sunset: &'static str, successor: &'static str) -> Self {
+        if let Some(endpoint) = self.endpoints.last_mut() {
+            endpoint.deprecation = Some(Deprecation { sunset, successor });
+        }
+        self
+    }
+
+    /// Stands in for a route whose feature flag is off, answering 404 on
+    /// its path instead of letting it fall through to an `{id}` route
+    /// registered after it. It is not listed by `GET /`.
+    pub fn switched_off(path: &'static str) -> Self {
+        ApiRoute {
+            path,
+            endpoints: Vec::new(),
+            resource: web::resource(path).default_service(web::to(not_found)),
+        }
+    }
+
+    /// Marks the most recently added method as safe to retry with an
+    /// `Idempotency-Key`; see the `idempotency` middleware.
+    pub fn idempotent(mut self) -> Self {
+        if let Some(endpoint) = self.endpoints.last_mut() {
+            endpoint.idempotent = true;
+        }
+        self
+    }
+}
+
+pub fn routes(features: &FeatureFlags) -> Vec<ApiRoute> {
+    let mut routes = vec![api_route(
+        "GET",
+        "/",
+        "List the available endpoints",
+        api_index,
+    )];
+    routes.extend(health_check::routes());
+    routes.extend(books::routes(features));
+    routes.extend(authors::routes(features));
+    routes.extend(users::routes(features));
+    routes.extend(admin::routes());
+    if features.is_enabled("graphql") {
+        routes.extend(graphql::routes());
+    }
+    routes
+}
+
+pub fn configure(cfg: &mut ServiceConfig, features: &FeatureFlags) {
+    let mut endpoints = Vec::new();
+    for route in routes(features) {
+        endpoints.extend(route.endpoints);
+        cfg.service(route.resource);
+    }
+    cfg.app_data(Data::new(endpoints));
+}
+
+pub async fn api_index(endpoints: Data<Vec<Endpoint>>) -> HttpResponse {
+    HttpResponse::Ok().json(endpoints.as_ref())
+}
+
+pub fn method_not_allowed(allowed: String) -> Route {
+    web::to(move || {
+        let allowed = allowed.clone();
+        async move {
+            let mut response = ApiError::MethodNotAllowed.error_response();
+            response.headers_mut().insert(
+                header::ALLOW,
+                header::HeaderValue::from_str(&allowed).expect("Invalid Allow header."),
+            );
+            response
+        }
+    })
+}
+
+/// Wraps `term` in `%` for a "contains" ILIKE, escaping its own wildcards.
+pub fn contains_pattern(term: &str) -> String {
+    format!(
+        "%{}%",
+        term.replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    )
+}
+
+/// Parses an `{id}` path segment, or an id sent in a body, answering 400
+/// `invalid_uuid` when it isn't one instead of silently looking up a nil id.
+pub fn parse_uuid_path(segment: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(segment).map_err(|_| ApiError::InvalidUuid(segment.to_string()))
+}
+
+pub async fn not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(error_envelope("not_found", "route not found"))
+}
@@ -1,12 +1,50 @@
-use crate::validations::user::NewUser;
+use crate::{
+    configuration::FeatureFlags,
+    db::{retrying, ReadPool, TimedPool, WritePool},
+    errors::ApiError,
+    json::Json,
+    routes::{api_route, parse_uuid_path, ApiRoute, LEGACY_ROUTES_SUNSET},
+    timezone::{localize, TimezoneQuery},
+    validations::user::NewUser,
+};
 use actix_web::{
-    web::{Data, Json},
-    HttpResponse,
+    http::header,
+    web::{Data, Path, Query},
+    HttpRequest, HttpResponse,
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use sqlx::PgPool;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+const USERS_EMAIL_UNIQUE: &str = "users_email_key";
+
+pub fn routes(features: &FeatureFlags) -> Vec<ApiRoute> {
+    let mut routes = vec![
+        api_route("POST", "/users", "Create a user", create_user),
+        api_route("GET", "/users/{user_id}", "Show a user", show_user),
+        api_route(
+            "POST",
+            "/users/{user_id}/deactivate",
+            "Deactivate a user, keeping their data",
+            deactivate_user,
+        ),
+        api_route(
+            "POST",
+            "/users/{user_id}/reactivate",
+            "Reactivate a deactivated user",
+            reactivate_user,
+        ),
+    ];
+    if features.is_enabled("legacy_routes") {
+        routes.push(
+            api_route("POST", "/users/create", "Create a user", create_user)
+                .deprecated(LEGACY_ROUTES_SUNSET, "POST /users"),
+        );
+    }
+    routes
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct NewUserData {
@@ -14,27 +52,129 @@ pub struct NewUserData {
     pub email: String,
 }
 
-pub async fn create_user(input: Json<NewUserData>, db_pool: Data<PgPool>) -> HttpResponse {
-    let new_user: NewUser = match input.0.try_into() {
-        Ok(value) => value,
-        Err(error) => return HttpResponse::BadRequest().body(error),
-    };
+pub async fn create_user(
+    req: HttpRequest,
+    input: Json<NewUserData>,
+    query: Query<TimezoneQuery>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+    let new_user: NewUser = input
+        .0
+        .try_into()
+        .map_err(|errors| ApiError::invalid(&req, errors))?;
+    let user = insert_user(&new_user, &db_pool.0)
+        .await
+        .map_err(email_conflict_error)?;
+
+    Ok(HttpResponse::Created()
+        .insert_header((header::LOCATION, format!("/users/{}", user.id)))
+        .json(localized_user(&user, tz)))
+}
+
+/// The error for a second user with an email that is already taken.
+pub(crate) fn email_conflict_error(error: sqlx::Error) -> ApiError {
+    match error {
+        sqlx::Error::Database(e) if e.constraint() == Some(USERS_EMAIL_UNIQUE) => {
+            ApiError::Conflict("A user with this email already exists.")
+        }
+        e => e.into(),
+    }
+}
+
+#[derive(Serialize)]
+pub struct UserRecord {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
 
-    match sqlx::query!(
+pub(crate) async fn insert_user(
+    new_user: &NewUser,
+    db_pool: &TimedPool,
+) -> Result<UserRecord, sqlx::Error> {
+    sqlx::query_as!(
+        UserRecord,
         "INSERT INTO users (name, email, created_at)
         VALUES ($1, $2, $3)
-        RETURNING id",
+        RETURNING id, name, email, is_active, created_at",
         new_user.name.as_ref(),
         new_user.email.as_ref(),
         Utc::now()
     )
-    .fetch_one(db_pool.get_ref())
+    .fetch_one(db_pool.named("create_user"))
     .await
-    {
-        Ok(record) => HttpResponse::Ok().json(json!({
-            "message": "User created successfully!",
-            "user_id": record.id
-        })),
-        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+}
+
+pub async fn show_user(
+    input: Path<String>,
+    query: Query<TimezoneQuery>,
+    db_pool: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = parse_uuid_path(&input)?;
+    let tz = query.parse().map_err(ApiError::bad_request)?;
+    let user = retrying("show_user", || {
+        sqlx::query_as!(
+            UserRecord,
+            "SELECT id, name, email, is_active, created_at FROM users WHERE id = $1",
+            user_id
+        )
+        .fetch_optional(&db_pool.0)
+    })
+    .await?
+    .ok_or(ApiError::NotFound("User not found"))?;
+
+    Ok(HttpResponse::Ok().json(localized_user(&user, tz)))
+}
+
+fn localized_user(user: &UserRecord, tz: Option<Tz>) -> Value {
+    let mut body = json!(user);
+    body["created_at"] = localize(user.created_at, tz);
+    body
+}
+
+pub async fn deactivate_user(
+    input: Path<String>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    set_user_active(&input, false, &db_pool.0).await
+}
+
+pub async fn reactivate_user(
+    input: Path<String>,
+    db_pool: Data<WritePool>,
+) -> Result<HttpResponse, ApiError> {
+    set_user_active(&input, true, &db_pool.0).await
+}
+
+// Either way the call is idempotent: switching a user to the state they are
+// already in still answers 200.
+async fn set_user_active(
+    input: &str,
+    is_active: bool,
+    db_pool: &TimedPool,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = parse_uuid_path(input)?;
+
+    let result = sqlx::query!(
+        "UPDATE users SET is_active = $2 WHERE id = $1",
+        user_id,
+        is_active
+    )
+    .execute(db_pool.named("set_user_active"))
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("User not found"));
     }
+    Ok(HttpResponse::Ok().json(json!({
+        "message": match is_active {
+            true => "User reactivated successfully!",
+            false => "User deactivated successfully!",
+        },
+        "user_id": user_id,
+        "is_active": is_active
+    })))
 }
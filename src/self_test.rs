@@ -0,0 +1,55 @@
+use crate::{
+    db::{begin, TimedPool},
+    routes::authors::{delete_author_record, insert_author, NewAuthorData},
+    validations::{author::NewAuthor, describe},
+};
+
+const SELF_TEST_AUTHOR: &str = "Self-test Author";
+
+/// Creates, reads and deletes an author inside a transaction that is always
+/// rolled back, so a broken schema or connection fails startup instead of
+/// the first request. Leaves no rows behind either way.
+pub async fn self_test(db_pool: &TimedPool) -> Result<(), String> {
+    let failed = |step: &str, e: sqlx::Error| format!("Self-test failed at '{}': {}", step, e);
+
+    let mut transaction = begin("self_test", db_pool)
+        .await
+        .map_err(|e| failed("begin", e))?;
+
+    let new_author = NewAuthor::try_from(NewAuthorData {
+        name: SELF_TEST_AUTHOR.to_string(),
+        nationality: "None".to_string(),
+    })
+    .map_err(|errors| format!("Self-test failed at 'validate': {}", describe(&errors)))?;
+    let author_id = insert_author(&new_author, &mut transaction)
+        .await
+        .map_err(|e| failed("create", e))?
+        .id;
+
+    let name = sqlx::query_scalar!("SELECT name FROM authors WHERE id = $1", author_id)
+        .fetch_one(&mut transaction)
+        .await
+        .map_err(|e| failed("read", e))?;
+    if name != SELF_TEST_AUTHOR {
+        return Err(format!(
+            "Self-test failed at 'read': expected '{}', got '{}'",
+            SELF_TEST_AUTHOR, name
+        ));
+    }
+
+    let deleted = delete_author_record(author_id, &mut transaction)
+        .await
+        .map_err(|e| failed("delete", e))?;
+    if !deleted {
+        return Err(String::from(
+            "Self-test failed at 'delete': the author was not found",
+        ));
+    }
+
+    transaction
+        .rollback()
+        .await
+        .map_err(|e| failed("rollback", e))?;
+    tracing::info!("Self-test passed: create, read and delete of an author succeeded");
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use crate::middleware::InFlight;
+use actix_web::{dev::ServerHandle, web::Data};
+
+/// Resolves on Ctrl-C or, on Unix, SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!(error = %e, "Failed to listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Waits for a shutdown signal, then stops accepting connections and lets
+/// the requests already running finish, up to the server's shutdown
+/// timeout. Closing the database pools is left to the caller, once the
+/// server future has resolved.
+pub async fn drain_on_shutdown(handle: ServerHandle, in_flight: Data<InFlight>) {
+    shutdown_signal().await;
+    tracing::info!(
+        in_flight = in_flight.count(),
+        "Shutting down, draining in-flight requests"
+    );
+
+    handle.stop(true).await;
+
+    match in_flight.count() {
+        0 => tracing::info!("All in-flight requests drained"),
+        abandoned => tracing::warn!(
+            abandoned,
+            "Shutdown timeout reached with requests still running"
+        ),
+    }
+}
@@ -0,0 +1,114 @@
+use serde::Deserialize;
+
+pub const BOOK_SORT_COLUMNS: [&str; 3] = ["title", "genre", "created_at"];
+pub const AUTHOR_SORT_COLUMNS: [&str; 3] = ["name", "nationality", "created_at"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortOrder(String);
+
+impl SortOrder {
+    /// Accepts a whitelisted column, optionally prefixed with `-` for
+    /// descending order.
+    pub fn parse(value: &str, columns: &[&str]) -> Result<Self, String> {
+        let column = value.strip_prefix('-').unwrap_or(value);
+
+        if columns.contains(&column) {
+            Ok(Self(value.to_string()))
+        } else {
+            Err(format!(
+                "'{}' is not a sortable column; expected one of {}.",
+                column,
+                columns.join(", ")
+            ))
+        }
+    }
+}
+
+impl AsRef<str> for SortOrder {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct DefaultSortConfig {
+    #[serde(default = "default_sort")]
+    pub books: String,
+    #[serde(default = "default_sort")]
+    pub authors: String,
+}
+
+fn default_sort() -> String {
+    String::from("created_at")
+}
+
+impl Default for DefaultSortConfig {
+    fn default() -> Self {
+        Self {
+            books: default_sort(),
+            authors: default_sort(),
+        }
+    }
+}
+
+pub struct DefaultSorts {
+    pub books: SortOrder,
+    pub authors: SortOrder,
+}
+
+impl TryFrom<&DefaultSortConfig> for DefaultSorts {
+    type Error = String;
+
+    fn try_from(value: &DefaultSortConfig) -> Result<Self, Self::Error> {
+        Ok(Self {
+            books: SortOrder::parse(&value.books, &BOOK_SORT_COLUMNS)?,
+            authors: SortOrder::parse(&value.authors, &AUTHOR_SORT_COLUMNS)?,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SortQuery {
+    pub sort: Option<String>,
+}
+
+impl SortQuery {
+    pub fn parse(&self, columns: &[&str], default: &SortOrder) -> Result<SortOrder, String> {
+        match &self.sort {
+            None => Ok(default.clone()),
+            Some(value) => SortOrder::parse(value, columns),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascending_and_descending_columns() {
+        assert!(SortOrder::parse("title", &BOOK_SORT_COLUMNS).is_ok());
+        assert!(SortOrder::parse("-title", &BOOK_SORT_COLUMNS).is_ok());
+    }
+
+    #[test]
+    fn unknown_column() {
+        assert!(SortOrder::parse("price", &BOOK_SORT_COLUMNS).is_err());
+    }
+
+    #[test]
+    fn invalid_default_is_rejected() {
+        let config = DefaultSortConfig {
+            books: String::from("title"),
+            authors: String::from("title"),
+        };
+        assert!(DefaultSorts::try_from(&config).is_err());
+    }
+
+    #[test]
+    fn query_falls_back_to_default() {
+        let default = SortOrder::parse("name", &AUTHOR_SORT_COLUMNS).unwrap();
+        let query = SortQuery { sort: None };
+        assert_eq!(query.parse(&AUTHOR_SORT_COLUMNS, &default), Ok(default));
+    }
+}
@@ -1,28 +1,107 @@
+use crate::configuration::ApplicationConfigs;
+use crate::db::{ReadPool, TimedPool, WritePool};
+use crate::errors::{json_config, path_config, query_config};
+use crate::graphql;
+use crate::json::StrictJson;
+use crate::middleware::{
+    access_log, admin_auth, body_log, concurrency_limit, deprecation_headers, idempotency,
+    maintenance_mode, purge_expired_keys_periodically, rate_limit, request_id_header,
+    request_timeout, track_in_flight, AccessLogFormat, AdminToken, ConcurrencyLimit, InFlight,
+    MaintenanceMode, RateLimiter, RequestTimeout,
+};
 use crate::routes;
+use crate::shutdown::drain_on_shutdown;
+use crate::sorting::DefaultSorts;
 use actix_web::dev::Server;
+use actix_web::middleware::{from_fn, Condition};
 use actix_web::{web, App, HttpServer};
 use sqlx::PgPool;
 use std::net::TcpListener;
+use std::time::Duration;
+use tracing_actix_web::TracingLogger;
 
-pub fn run(address: TcpListener, db_pool: PgPool) -> Result<Server, std::io::Error> {
-    let db_pool = web::Data::new(db_pool);
+pub fn run(
+    address: TcpListener,
+    db_pool: PgPool,
+    replica_pool: Option<PgPool>,
+    config: &ApplicationConfigs,
+) -> Result<Server, std::io::Error> {
+    let read_pool = web::Data::new(ReadPool(TimedPool::new(
+        replica_pool.unwrap_or_else(|| db_pool.clone()),
+    )));
+    let purge_pool = TimedPool::new(db_pool.clone());
+    let write_pool = web::Data::new(WritePool(TimedPool::new(db_pool)));
+    let request_timeout_limit = web::Data::new(RequestTimeout(Duration::from_millis(
+        config.request_timeout_ms,
+    )));
+    let schema = web::Data::new(graphql::build_schema(read_pool.clone(), write_pool.clone()));
+    let default_sorts = DefaultSorts::try_from(&config.default_sort)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let default_sorts = web::Data::new(default_sorts);
+    let bulk_limits = web::Data::new(config.bulk_limits.clone());
+    let maintenance = web::Data::new(MaintenanceMode::new(config.maintenance_mode));
+    let admin_token = web::Data::new(AdminToken(config.admin_token.clone()));
+    let combined_access_log = config.access_log == AccessLogFormat::Combined;
+    let body_log_enabled = config.debug_body_log.enabled;
+    if body_log_enabled {
+        tracing::warn!("Request and response bodies are being logged (debug_body_log.enabled)");
+    }
+    let body_log_config = web::Data::new(config.debug_body_log.clone());
+    let concurrency_limited = config.max_concurrent_requests.is_some();
+    let concurrency = web::Data::new(ConcurrencyLimit::new(
+        config.max_concurrent_requests.unwrap_or(0),
+    ));
+    let rate_limited = config.rate_limit.enabled;
+    let rate_limiter = web::Data::new(
+        RateLimiter::new(&config.rate_limit)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?,
+    );
+    let strict_json = web::Data::new(StrictJson(config.strict_json));
+    let in_flight = web::Data::new(InFlight::default());
+    let in_flight_count = in_flight.clone();
+    let features = config.features.clone();
     let server = HttpServer::new(move || {
         App::new()
-            .route("/health_check", web::get().to(routes::health_check))
-            .route("/books", web::get().to(routes::books_index))
-            .route("/books/{book_id}", web::get().to(routes::show_book))
-            .route("/books/create", web::post().to(routes::create_book))
-            .route("/books/delete", web::post().to(routes::delete_book))
-            .route("/authors", web::get().to(routes::authors_index))
-            .route("/authors/{author_id}", web::get().to(routes::show_author))
-            .route("/authors/create", web::post().to(routes::create_author))
-            .route("/authors/delete", web::post().to(routes::delete_author))
-            .route("/users/create", web::post().to(routes::create_user))
-            .route("/seed_authors", web::get().to(routes::seed_authors))
-            .app_data(db_pool.clone())
+            .wrap(from_fn(idempotency))
+            .wrap(from_fn(deprecation_headers))
+            .wrap(from_fn(track_in_flight))
+            .wrap(from_fn(maintenance_mode))
+            .wrap(from_fn(admin_auth))
+            .wrap(from_fn(request_timeout))
+            .wrap(Condition::new(
+                concurrency_limited,
+                from_fn(concurrency_limit),
+            ))
+            .wrap(Condition::new(rate_limited, from_fn(rate_limit)))
+            .wrap(Condition::new(combined_access_log, from_fn(access_log)))
+            .wrap(Condition::new(body_log_enabled, from_fn(body_log)))
+            .wrap(from_fn(request_id_header))
+            .wrap(TracingLogger::default())
+            .configure(|cfg| routes::configure(cfg, &features))
+            .default_service(web::to(routes::not_found))
+            .app_data(read_pool.clone())
+            .app_data(write_pool.clone())
+            .app_data(request_timeout_limit.clone())
+            .app_data(maintenance.clone())
+            .app_data(admin_token.clone())
+            .app_data(schema.clone())
+            .app_data(default_sorts.clone())
+            .app_data(bulk_limits.clone())
+            .app_data(body_log_config.clone())
+            .app_data(concurrency.clone())
+            .app_data(rate_limiter.clone())
+            .app_data(strict_json.clone())
+            .app_data(in_flight.clone())
+            .app_data(json_config())
+            .app_data(query_config())
+            .app_data(path_config())
     })
+    .shutdown_timeout(config.shutdown_timeout_secs)
+    .disable_signals()
     .listen(address)?
     .run();
+    tokio::spawn(drain_on_shutdown(server.handle(), in_flight_count));
+    tokio::spawn(purge_expired_keys_periodically(purge_pool));
 
     Ok(server)
 }
@@ -0,0 +1,50 @@
+use tracing::{subscriber::set_global_default, Subscriber};
+use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
+use tracing_log::LogTracer;
+use tracing_subscriber::{
+    filter::ParseError, fmt::MakeWriter, layer::SubscriberExt, EnvFilter, Registry,
+};
+
+/// `RUST_LOG` wins over the configured `log_filter`, so verbosity can be
+/// raised for one run without touching the configuration files.
+pub fn env_filter(log_filter: &str) -> Result<EnvFilter, ParseError> {
+    match std::env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(directives) => EnvFilter::try_new(directives),
+        Err(_) => EnvFilter::try_new(log_filter),
+    }
+}
+
+/// One Bunyan JSON line per event, carrying the fields of every enclosing
+/// span: the request id and route from `TracingLogger`, and the operation
+/// name of the database query being run.
+pub fn get_subscriber<Sink>(
+    name: &str,
+    env_filter: EnvFilter,
+    sink: Sink,
+) -> impl Subscriber + Send + Sync
+where
+    Sink: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    Registry::default()
+        .with(env_filter)
+        .with(JsonStorageLayer)
+        .with(BunyanFormattingLayer::new(name.into(), sink))
+}
+
+/// Also routes `log` records (sqlx statements, actix internals) through
+/// the subscriber. Can only be called once per process.
+pub fn init_subscriber(subscriber: impl Subscriber + Send + Sync) {
+    LogTracer::init().expect("Failed to set the log logger.");
+    set_global_default(subscriber).expect("Failed to set the tracing subscriber.");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_malformed_filters() {
+        assert!(EnvFilter::try_new("info,sqlx=warn").is_ok());
+        assert!(EnvFilter::try_new("sqlx=loud").is_err());
+    }
+}
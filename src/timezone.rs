@@ -0,0 +1,78 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+#[derive(Deserialize)]
+pub struct TimezoneQuery {
+    pub tz: Option<String>,
+}
+
+impl TimezoneQuery {
+    pub fn parse(&self) -> Result<Option<Tz>, String> {
+        match &self.tz {
+            None => Ok(None),
+            Some(name) => name
+                .parse::<Tz>()
+                .map(Some)
+                .map_err(|_| format!("'{}' is not a valid IANA timezone.", name)),
+        }
+    }
+}
+
+pub fn localize(timestamp: DateTime<Utc>, tz: Option<Tz>) -> Value {
+    match tz {
+        Some(tz) => json!(timestamp.with_timezone(&tz).fixed_offset()),
+        None => json!(timestamp),
+    }
+}
+
+/// The same conversion for CSV cells.
+pub fn localize_rfc3339(timestamp: DateTime<Utc>, tz: Option<Tz>) -> String {
+    match tz {
+        Some(tz) => timestamp.with_timezone(&tz).to_rfc3339(),
+        None => timestamp.to_rfc3339(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn missing_timezone_defaults_to_utc() {
+        let query = TimezoneQuery { tz: None };
+        assert!(query.parse().unwrap().is_none());
+    }
+
+    #[test]
+    fn valid_timezone() {
+        let query = TimezoneQuery {
+            tz: Some(String::from("America/Sao_Paulo")),
+        };
+        assert_eq!(query.parse().unwrap(), Some(Tz::America__Sao_Paulo));
+    }
+
+    #[test]
+    fn invalid_timezone() {
+        let query = TimezoneQuery {
+            tz: Some(String::from("Mars/Olympus_Mons")),
+        };
+        assert!(query.parse().is_err());
+    }
+
+    #[test]
+    fn localize_converts_offset() {
+        let timestamp = Utc.with_ymd_and_hms(2024, 3, 10, 10, 0, 0).unwrap();
+        assert_eq!(
+            localize(timestamp, Some(Tz::America__Sao_Paulo)),
+            "2024-03-10T07:00:00-03:00"
+        );
+        assert_eq!(localize(timestamp, None), "2024-03-10T10:00:00Z");
+        assert_eq!(
+            localize_rfc3339(timestamp, Some(Tz::America__Sao_Paulo)),
+            "2024-03-10T07:00:00-03:00"
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use crate::routes::NewAuthorData;
+use crate::{
+    routes::authors::{NewAuthorData, UpdateAuthorData},
+    validations::{ValidationError, MAX_AUTHOR_NAME_LENGTH, MAX_AUTHOR_NATIONALITY_LENGTH},
+};
 
 pub struct NewAuthor {
     pub name: ValidatedAuthorName,
@@ -6,24 +9,68 @@ pub struct NewAuthor {
 }
 
 impl TryFrom<NewAuthorData> for NewAuthor {
-    type Error = String;
+    type Error = Vec<ValidationError>;
 
     fn try_from(value: NewAuthorData) -> Result<Self, Self::Error> {
-        let name = ValidatedAuthorName::new(value.name)?;
-        let nationality = ValidatedAuthorNationality::new(value.nationality)?;
-        Ok(Self { name, nationality })
+        match (
+            ValidatedAuthorName::new(value.name).map_err(|e| e.on("name")),
+            ValidatedAuthorNationality::new(value.nationality).map_err(|e| e.on("nationality")),
+        ) {
+            (Ok(name), Ok(nationality)) => Ok(Self { name, nationality }),
+            (name, nationality) => Err([name.err(), nationality.err()]
+                .into_iter()
+                .flatten()
+                .collect()),
+        }
+    }
+}
+
+/// A partial update: only the fields that were sent are changed.
+pub struct AuthorUpdate {
+    pub name: Option<ValidatedAuthorName>,
+    pub nationality: Option<ValidatedAuthorNationality>,
+}
+
+impl AuthorUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.nationality.is_none()
+    }
+}
+
+impl TryFrom<UpdateAuthorData> for AuthorUpdate {
+    type Error = Vec<ValidationError>;
+
+    fn try_from(value: UpdateAuthorData) -> Result<Self, Self::Error> {
+        match (
+            value
+                .name
+                .map(ValidatedAuthorName::new)
+                .transpose()
+                .map_err(|e| e.on("name")),
+            value
+                .nationality
+                .map(ValidatedAuthorNationality::new)
+                .transpose()
+                .map_err(|e| e.on("nationality")),
+        ) {
+            (Ok(name), Ok(nationality)) => Ok(Self { name, nationality }),
+            (name, nationality) => Err([name.err(), nationality.err()]
+                .into_iter()
+                .flatten()
+                .collect()),
+        }
     }
 }
 
 pub struct ValidatedAuthorName(String);
 
 impl ValidatedAuthorName {
-    pub fn new(value: String) -> Result<Self, String> {
+    pub fn new(value: String) -> Result<Self, ValidationError> {
         let is_empty_or_whitespace = value.trim().is_empty();
-        let size_too_big = value.chars().count() > 256;
+        let size_too_big = value.chars().count() > MAX_AUTHOR_NAME_LENGTH;
 
         if is_empty_or_whitespace || size_too_big {
-            Err(format!("'{}' is not a valid author name.", value))
+            Err(ValidationError::new("invalid_author_name", value))
         } else {
             Ok(Self(value))
         }
@@ -39,12 +86,12 @@ impl AsRef<str> for ValidatedAuthorName {
 pub struct ValidatedAuthorNationality(String);
 
 impl ValidatedAuthorNationality {
-    fn new(value: String) -> Result<Self, String> {
+    fn new(value: String) -> Result<Self, ValidationError> {
         let is_empty_or_whitespace = value.trim().is_empty();
-        let size_too_big = value.chars().count() > 80;
+        let size_too_big = value.chars().count() > MAX_AUTHOR_NATIONALITY_LENGTH;
 
         if is_empty_or_whitespace || size_too_big {
-            Err(format!("'{}' is not a valid author nationality.", value))
+            Err(ValidationError::new("invalid_author_nationality", value))
         } else {
             Ok(Self(value))
         }
@@ -81,13 +128,13 @@ mod tests {
 
     #[test]
     fn max_length_name() {
-        let name = "a".repeat(256);
+        let name = "a".repeat(MAX_AUTHOR_NAME_LENGTH);
         assert!(ValidatedAuthorName::new(name).is_ok());
     }
 
     #[test]
     fn too_long_name() {
-        let name = "a".repeat(257);
+        let name = "a".repeat(MAX_AUTHOR_NAME_LENGTH + 1);
         assert!(ValidatedAuthorName::new(name).is_err());
     }
 
@@ -111,16 +158,36 @@ mod tests {
 
     #[test]
     fn max_length_nationality() {
-        let nationality = "a".repeat(80);
+        let nationality = "a".repeat(MAX_AUTHOR_NATIONALITY_LENGTH);
         assert!(ValidatedAuthorNationality::new(nationality).is_ok());
     }
 
     #[test]
     fn too_long_nationality() {
-        let nationality = "a".repeat(81);
+        let nationality = "a".repeat(MAX_AUTHOR_NATIONALITY_LENGTH + 1);
         assert!(ValidatedAuthorNationality::new(nationality).is_err());
     }
 
+    #[test]
+    fn author_update_keeps_missing_fields_unset() {
+        let data = UpdateAuthorData {
+            name: None,
+            nationality: Some(String::from("British")),
+        };
+        let update = AuthorUpdate::try_from(data).unwrap();
+        assert!(update.name.is_none());
+        assert!(!update.is_empty());
+    }
+
+    #[test]
+    fn author_update_validates_sent_fields() {
+        let data = UpdateAuthorData {
+            name: Some(String::from(" ")),
+            nationality: None,
+        };
+        assert!(AuthorUpdate::try_from(data).is_err());
+    }
+
     #[test]
     fn new_author_success() {
         let data = NewAuthorData {
@@ -130,6 +197,17 @@ mod tests {
         assert!(NewAuthor::try_from(data).is_ok());
     }
 
+    #[test]
+    fn new_author_reports_every_invalid_field() {
+        let data = NewAuthorData {
+            name: String::from(" "),
+            nationality: String::from(""),
+        };
+        let errors = NewAuthor::try_from(data).err().unwrap();
+        let fields: Vec<_> = errors.iter().map(|error| error.field).collect();
+        assert_eq!(fields, [Some("name"), Some("nationality")]);
+    }
+
     #[test]
     fn new_author_failure() {
         let data = NewAuthorData {
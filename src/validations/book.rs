@@ -1,36 +1,97 @@
-use crate::{routes::NewBookData, validations::author::ValidatedAuthorName};
+use crate::{
+    routes::books::{NewBookData, UpdateBookData},
+    validations::{
+        author::ValidatedAuthorName, ValidationError, MAX_BOOK_GENRE_LENGTH, MAX_BOOK_TITLE_LENGTH,
+    },
+};
+use uuid::Uuid;
+
+/// Books name their author either by id or by exact name.
+pub enum BookAuthor {
+    Id(Uuid),
+    Name(ValidatedAuthorName),
+}
 
 pub struct NewBook {
     pub title: ValidatedBookTitle,
-    pub author: ValidatedAuthorName,
+    pub author: BookAuthor,
     pub genre: ValidatedBookGenre,
+    pub parent_book_id: Option<Uuid>,
 }
 
 impl TryFrom<NewBookData> for NewBook {
-    type Error = String;
+    type Error = Vec<ValidationError>;
 
     fn try_from(value: NewBookData) -> Result<Self, Self::Error> {
-        let title = ValidatedBookTitle::new(value.title)?;
-        let author = ValidatedAuthorName::new(value.author)?;
-        let genre = ValidatedBookGenre::new(value.genre)?;
-
-        Ok(Self {
-            title,
+        // An id wins over a name, which is then not even validated.
+        let author = match value.author_id {
+            Some(author_id) => Ok(BookAuthor::Id(author_id)),
+            None => ValidatedAuthorName::new(value.author)
+                .map(BookAuthor::Name)
+                .map_err(|e| e.on("author")),
+        };
+        match (
+            ValidatedBookTitle::new(value.title).map_err(|e| e.on("title")),
             author,
-            genre,
-        })
+            ValidatedBookGenre::new(value.genre).map_err(|e| e.on("genre")),
+        ) {
+            (Ok(title), Ok(author), Ok(genre)) => Ok(Self {
+                title,
+                author,
+                genre,
+                parent_book_id: value.parent_book_id,
+            }),
+            (title, author, genre) => Err([title.err(), author.err(), genre.err()]
+                .into_iter()
+                .flatten()
+                .collect()),
+        }
+    }
+}
+
+/// A partial update: only the fields that were sent are changed.
+pub struct BookUpdate {
+    pub title: Option<ValidatedBookTitle>,
+    pub genre: Option<ValidatedBookGenre>,
+}
+
+impl BookUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.genre.is_none()
+    }
+}
+
+impl TryFrom<UpdateBookData> for BookUpdate {
+    type Error = Vec<ValidationError>;
+
+    fn try_from(value: UpdateBookData) -> Result<Self, Self::Error> {
+        match (
+            value
+                .title
+                .map(ValidatedBookTitle::new)
+                .transpose()
+                .map_err(|e| e.on("title")),
+            value
+                .genre
+                .map(ValidatedBookGenre::new)
+                .transpose()
+                .map_err(|e| e.on("genre")),
+        ) {
+            (Ok(title), Ok(genre)) => Ok(Self { title, genre }),
+            (title, genre) => Err([title.err(), genre.err()].into_iter().flatten().collect()),
+        }
     }
 }
 
 pub struct ValidatedBookTitle(String);
 
 impl ValidatedBookTitle {
-    pub fn new(title: String) -> Result<Self, String> {
+    pub fn new(title: String) -> Result<Self, ValidationError> {
         let is_empty_or_whitespace = title.trim().is_empty();
-        let size_too_big = title.chars().count() > 256;
+        let size_too_big = title.chars().count() > MAX_BOOK_TITLE_LENGTH;
 
         if is_empty_or_whitespace || size_too_big {
-            Err(format!("'{}' is not a valid author title.", title))
+            Err(ValidationError::new("invalid_book_title", title))
         } else {
             Ok(Self(title))
         }
@@ -46,12 +107,12 @@ impl AsRef<str> for ValidatedBookTitle {
 pub struct ValidatedBookGenre(String);
 
 impl ValidatedBookGenre {
-    fn new(genre: String) -> Result<Self, String> {
+    pub fn new(genre: String) -> Result<Self, ValidationError> {
         let is_empty_or_whitespace = genre.trim().is_empty();
-        let size_too_big = genre.chars().count() > 80;
+        let size_too_big = genre.chars().count() > MAX_BOOK_GENRE_LENGTH;
 
         if is_empty_or_whitespace || size_too_big {
-            Err(format!("'{}' is not a valid author genre.", genre))
+            Err(ValidationError::new("invalid_book_genre", genre))
         } else {
             Ok(Self(genre))
         }
@@ -88,13 +149,13 @@ mod tests {
 
     #[test]
     fn max_length_title() {
-        let title = "a".repeat(256);
+        let title = "a".repeat(MAX_BOOK_TITLE_LENGTH);
         assert!(ValidatedBookTitle::new(title).is_ok());
     }
 
     #[test]
     fn too_long_tile() {
-        let title = "a".repeat(257);
+        let title = "a".repeat(MAX_BOOK_TITLE_LENGTH + 1);
         assert!(ValidatedBookTitle::new(title).is_err());
     }
 
@@ -118,13 +179,13 @@ mod tests {
 
     #[test]
     fn max_length_genre() {
-        let genre = "a".repeat(80);
+        let genre = "a".repeat(MAX_BOOK_GENRE_LENGTH);
         assert!(ValidatedBookGenre::new(genre).is_ok());
     }
 
     #[test]
     fn too_long_genre() {
-        let genre = "a".repeat(81);
+        let genre = "a".repeat(MAX_BOOK_GENRE_LENGTH + 1);
         assert!(ValidatedBookGenre::new(genre).is_err());
     }
 
@@ -133,18 +194,69 @@ mod tests {
         let data = NewBookData {
             title: String::from("Pride and Prejudice"),
             author: String::from("Jane Austen"),
+            author_id: None,
             genre: String::from("British"),
+            parent_book_id: None,
         };
         assert!(NewBook::try_from(data).is_ok());
     }
 
+    #[test]
+    fn new_book_by_author_id_needs_no_name() {
+        let data = NewBookData {
+            title: String::from("Pride and Prejudice"),
+            author: String::new(),
+            author_id: Some(Uuid::nil()),
+            genre: String::from("British"),
+            parent_book_id: None,
+        };
+        assert!(matches!(
+            NewBook::try_from(data).map(|book| book.author),
+            Ok(BookAuthor::Id(_))
+        ));
+    }
+
     #[test]
     fn new_book_failure() {
         let data = NewBookData {
             title: String::from("Pride \\(and) Prejudice"),
             author: String::from(""),
+            author_id: None,
             genre: String::from("Britisn"),
+            parent_book_id: None,
         };
         assert!(NewBook::try_from(data).is_err());
     }
+
+    #[test]
+    fn new_book_collects_every_error() {
+        let data = NewBookData {
+            title: String::from(""),
+            author: String::from(""),
+            author_id: None,
+            genre: String::from("Fiction"),
+            parent_book_id: None,
+        };
+        assert_eq!(
+            NewBook::try_from(data).err().map(|errors| errors.len()),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn book_update_validates_sent_fields() {
+        let data = UpdateBookData {
+            title: Some(String::from(" ")),
+            genre: None,
+        };
+        assert!(BookUpdate::try_from(data).is_err());
+
+        let data = UpdateBookData {
+            title: None,
+            genre: Some(String::from("Fantasy")),
+        };
+        let update = BookUpdate::try_from(data).unwrap();
+        assert!(update.title.is_none());
+        assert!(!update.is_empty());
+    }
 }
@@ -1,3 +1,77 @@
 pub mod author;
 pub mod book;
 pub mod user;
+
+use crate::locale::Locale;
+use serde_json::{json, Value};
+use std::fmt;
+
+// These limits are mirrored by CHECK constraints in the
+// add_length_constraints migration; keep both in sync.
+pub const MAX_AUTHOR_NAME_LENGTH: usize = 256;
+pub const MAX_AUTHOR_NATIONALITY_LENGTH: usize = 80;
+pub const MAX_BOOK_TITLE_LENGTH: usize = 256;
+pub const MAX_BOOK_GENRE_LENGTH: usize = 80;
+pub const MAX_USER_NAME_LENGTH: usize = 256;
+pub const MAX_USER_EMAIL_LENGTH: usize = 90;
+
+/// A rejected input, identified by a stable `code` so the message can be
+/// rendered in the caller's language.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    pub code: &'static str,
+    pub value: String,
+    /// The payload key the value came from, when there is one.
+    pub field: Option<&'static str>,
+}
+
+impl ValidationError {
+    pub fn new(code: &'static str, value: impl Into<String>) -> Self {
+        Self {
+            code,
+            value: value.into(),
+            field: None,
+        }
+    }
+
+    /// Set where a payload is mapped rather than in the newtypes, since the
+    /// same one can back differently named keys (an alias is an author name).
+    pub fn on(mut self, field: &'static str) -> Self {
+        self.field = Some(field);
+        self
+    }
+
+    pub fn localized(&self, locale: Locale) -> String {
+        locale.message(self.code, &self.value)
+    }
+
+    pub fn to_json(&self, locale: Locale) -> Value {
+        let mut json = json!({"code": self.code, "message": self.localized(locale)});
+        if let Some(field) = self.field {
+            json["field"] = field.into();
+        }
+        json
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localized(Locale::En))
+    }
+}
+
+/// All the messages in English, for callers without a response to fill in
+/// field by field.
+pub fn describe(errors: &[ValidationError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+impl From<ValidationError> for String {
+    fn from(error: ValidationError) -> Self {
+        error.to_string()
+    }
+}
@@ -1,4 +1,7 @@
-use crate::routes::NewUserData;
+use crate::{
+    routes::users::NewUserData,
+    validations::{ValidationError, MAX_USER_EMAIL_LENGTH, MAX_USER_NAME_LENGTH},
+};
 use regex::Regex;
 
 pub struct NewUser {
@@ -7,24 +10,28 @@ pub struct NewUser {
 }
 
 impl TryFrom<NewUserData> for NewUser {
-    type Error = String;
+    type Error = Vec<ValidationError>;
 
     fn try_from(value: NewUserData) -> Result<Self, Self::Error> {
-        let name = ValidatedUserName::new(value.name)?;
-        let email = ValidatedUserEmail::new(value.email)?;
-        Ok(Self { name, email })
+        match (
+            ValidatedUserName::new(value.name).map_err(|e| e.on("name")),
+            ValidatedUserEmail::new(value.email).map_err(|e| e.on("email")),
+        ) {
+            (Ok(name), Ok(email)) => Ok(Self { name, email }),
+            (name, email) => Err([name.err(), email.err()].into_iter().flatten().collect()),
+        }
     }
 }
 
 pub struct ValidatedUserName(String);
 
 impl ValidatedUserName {
-    pub fn new(value: String) -> Result<Self, String> {
+    pub fn new(value: String) -> Result<Self, ValidationError> {
         let is_empty_or_whitespace = value.trim().is_empty();
-        let size_too_big = value.chars().count() > 256;
+        let size_too_big = value.chars().count() > MAX_USER_NAME_LENGTH;
 
         if is_empty_or_whitespace || size_too_big {
-            Err(format!("'{}' is not a valid user name.", value))
+            Err(ValidationError::new("invalid_user_name", value))
         } else {
             Ok(Self(value))
         }
@@ -40,15 +47,15 @@ impl AsRef<str> for ValidatedUserName {
 pub struct ValidatedUserEmail(String);
 
 impl ValidatedUserEmail {
-    fn new(value: String) -> Result<Self, String> {
+    fn new(value: String) -> Result<Self, ValidationError> {
         let is_empty_or_whitespace = value.trim().is_empty();
-        let size_too_big = value.chars().count() > 90;
+        let size_too_big = value.chars().count() > MAX_USER_EMAIL_LENGTH;
 
         // https://html.spec.whatwg.org/multipage/input.html#valid-e-mail-address
         let email_regex = Regex::new(r"^[a-zA-Z0-9.!#$%&'*+/=?^_`{|}~-]+@[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?(?:\.[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])+)+$").unwrap();
 
         if is_empty_or_whitespace || size_too_big || !email_regex.is_match(&value) {
-            Err(format!("'{}' is not a valid user email.", value))
+            Err(ValidationError::new("invalid_user_email", value))
         } else {
             Ok(Self(value))
         }
@@ -88,7 +95,7 @@ mod tests {
 
     #[test]
     fn invalid_user_name_too_long() {
-        let name = "a".repeat(257);
+        let name = "a".repeat(MAX_USER_NAME_LENGTH + 1);
         let user_name = ValidatedUserName::new(name);
         assert!(user_name.is_err());
     }
@@ -143,7 +150,7 @@ mod tests {
 
     #[test]
     fn email_invalid_length() {
-        let email = "a".repeat(90);
+        let email = "a".repeat(MAX_USER_EMAIL_LENGTH);
         assert!(ValidatedUserEmail::new(format!("{email}@example-domain.com")).is_err());
     }
 
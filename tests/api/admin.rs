@@ -0,0 +1,486 @@
+use crate::test_helpers::{drop_db, spawn_app, spawn_app_with, ADMIN_TOKEN};
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn reassign_genre() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await;
+    app.create_book(r#"{"title":"Dune", "author":"Frank Herbert", "genre": "Sci-Fi"}"#.into())
+        .await;
+    app.create_book(
+        r#"{"title":"Dune Messiah", "author":"Frank Herbert", "genre": "Sci-Fi"}"#.into(),
+    )
+    .await;
+    app.create_book(
+        r#"{"title":"The Dosadi Experiment", "author":"Frank Herbert", "genre": "Fiction"}"#.into(),
+    )
+    .await;
+
+    let response = app
+        .reassign_genre(r#"{"from": "Sci-Fi", "to": "Science Fiction"}"#.into(), "")
+        .await;
+    let response_body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let records = sqlx::query!("SELECT genre FROM books WHERE genre = 'Science Fiction'")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved books.");
+
+    assert_eq!(response_body["books_updated"], 2);
+    assert_eq!(records.len(), 2);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn reassign_genre_with_empty_genre() {
+    let app = spawn_app().await;
+
+    let response = app
+        .reassign_genre(r#"{"from": "Sci-Fi", "to": "  "}"#.into(), "")
+        .await;
+
+    assert_eq!(response.status().as_u16(), 422);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn reassign_genre_dry_run() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await;
+    app.create_book(r#"{"title":"Dune", "author":"Frank Herbert", "genre": "Sci-Fi"}"#.into())
+        .await;
+
+    let response = app
+        .reassign_genre(
+            r#"{"from": "Sci-Fi", "to": "Science Fiction"}"#.into(),
+            "dry_run=true",
+        )
+        .await;
+    let response_body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let record = sqlx::query!("SELECT genre FROM books")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved book.");
+
+    assert_eq!(response_body["dry_run"], true);
+    assert_eq!(response_body["books_updated"], 1);
+    assert_eq!(record.genre, "Sci-Fi");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn tag_books_by_title_and_author() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await;
+    app.create_author(r#"{"name":"Brian Herbert", "nationality":"American"}"#.into())
+        .await;
+    app.create_book(r#"{"title":"Dune", "author":"Frank Herbert", "genre": "Fiction"}"#.into())
+        .await;
+    app.create_book(
+        r#"{"title":"Dune Messiah", "author":"Frank Herbert", "genre": "Fiction"}"#.into(),
+    )
+    .await;
+    app.create_book(
+        r#"{"title":"Dune: House Atreides", "author":"Brian Herbert", "genre": "Fiction"}"#.into(),
+    )
+    .await;
+    let author_id = sqlx::query!("SELECT id FROM authors WHERE name = 'Frank Herbert'")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved author.")
+        .id;
+
+    let response = app
+        .tag_books(
+            json!({"title_contains": "dune", "author_id": author_id, "genre": "Sci-Fi"}),
+            "",
+        )
+        .await;
+    let response_body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let records = sqlx::query!("SELECT title FROM books WHERE genre = 'Sci-Fi' ORDER BY title")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved books.");
+
+    assert_eq!(response_body["books_updated"], 2);
+    assert_eq!(records[0].title, "Dune");
+    assert_eq!(records[1].title, "Dune Messiah");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn tag_books_dry_run() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await;
+    app.create_book(r#"{"title":"Dune", "author":"Frank Herbert", "genre": "Fiction"}"#.into())
+        .await;
+
+    let response = app
+        .tag_books(
+            json!({"title_contains": "Dune", "genre": "Sci-Fi"}),
+            "dry_run=true",
+        )
+        .await;
+    let response_body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let record = sqlx::query!("SELECT genre FROM books")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved book.");
+
+    assert_eq!(response_body["dry_run"], true);
+    assert_eq!(response_body["books_updated"], 1);
+    assert_eq!(record.genre, "Fiction");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn tag_books_requires_a_filter() {
+    let app = spawn_app().await;
+
+    let response = app.tag_books(json!({"genre": "Sci-Fi"}), "").await;
+
+    assert_eq!(response.status().as_u16(), 400);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn tag_books_requires_the_admin_token() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await;
+    app.create_book(r#"{"title":"Dune", "author":"Frank Herbert", "genre": "Fiction"}"#.into())
+        .await;
+
+    let response = reqwest::Client::new()
+        .post(format!("http://{}/admin/books/tag", &app.address))
+        .json(&json!({"title_contains": "Dune", "genre": "Sci-Fi"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let record = sqlx::query!("SELECT genre FROM books")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved book.");
+
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(record.genre, "Fiction");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn maintenance_mode_rejects_writes() {
+    let app = spawn_app().await;
+
+    app.set_maintenance_mode(true).await;
+    let create_response = app
+        .create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await;
+    let index_response = app.author_index().await;
+
+    assert_eq!(create_response.status().as_u16(), 503);
+    assert!(create_response.headers().contains_key("Retry-After"));
+    assert_eq!(index_response.status().as_u16(), 200);
+    let body = create_response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    assert_eq!(body["error"]["code"], "service_unavailable");
+
+    app.set_maintenance_mode(false).await;
+    let create_response = app
+        .create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await;
+
+    assert_eq!(create_response.status().as_u16(), 201);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn maintenance_mode_allows_graphql_queries_only() {
+    let app = spawn_app().await;
+    app.set_maintenance_mode(true).await;
+
+    let query = app.graphql(json!({"query": "{ authors { name } }"})).await;
+    let query_status = query.status().as_u16();
+    let query_body = query
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let mutation = app
+        .graphql(json!({
+            "query": r#"mutation { createAuthor(name: "Frank Herbert", nationality: "American") }"#
+        }))
+        .await;
+    let mutation_status = mutation.status().as_u16();
+    let mutation_body = mutation
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(query_status, 200);
+    assert_eq!(query_body["data"]["authors"], json!([]));
+    assert_eq!(mutation_status, 503);
+    assert_eq!(mutation_body["error"]["code"], "service_unavailable");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn backup_and_restore_with_truncate() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await;
+    app.create_book(r#"{"title":"Dune", "author":"Frank Herbert", "genre": "Sci-Fi"}"#.into())
+        .await;
+    app.create_user(r#"{"name":"Paul Atreides", "email":"paul@arrakis.com"}"#.into())
+        .await;
+
+    let backup = app
+        .backup()
+        .await
+        .text()
+        .await
+        .expect("Failed to read backup.");
+    let backup_body = serde_json::from_str::<Value>(&backup).expect("Backup is not valid JSON.");
+    app.create_author(r#"{"name":"Isaac Asimov", "nationality":"American"}"#.into())
+        .await;
+
+    let response = app.restore(backup, "truncate=true").await;
+    let authors = sqlx::query!("SELECT id, name FROM authors")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved authors.");
+    let book = sqlx::query!("SELECT title, author_id FROM books")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved book.");
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(backup_body["users"][0]["email"], "paul@arrakis.com");
+    assert_eq!(authors.len(), 1);
+    assert_eq!(authors[0].name, "Frank Herbert");
+    assert_eq!(authors[0].id.to_string(), backup_body["authors"][0]["id"]);
+    assert_eq!(book.title, "Dune");
+    assert_eq!(book.author_id, authors[0].id);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn restore_upserts_by_default() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await;
+    let backup = app
+        .backup()
+        .await
+        .text()
+        .await
+        .expect("Failed to read backup.");
+    sqlx::query!("UPDATE authors SET name = 'Renamed'")
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to rename author.");
+    app.create_author(r#"{"name":"Isaac Asimov", "nationality":"American"}"#.into())
+        .await;
+
+    let response = app.restore(backup, "").await;
+    let names = sqlx::query!("SELECT name FROM authors ORDER BY name")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved authors.")
+        .into_iter()
+        .map(|record| record.name)
+        .collect::<Vec<String>>();
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(names, vec!["Frank Herbert", "Isaac Asimov"]);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn restore_with_dangling_author_rolls_back() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await;
+    let body = r#"{
+        "authors": [],
+        "books": [{
+            "id": "a56de2a8-61d3-43f4-b66b-b454c2b54589",
+            "title": "Dune",
+            "genre": "Sci-Fi",
+            "author_id": "82648e74-3fb4-4fe2-a4a2-5f6db5d20d3b",
+            "created_at": "2024-03-10T10:22:58.244130Z",
+            "updated_at": "2024-03-10T10:22:58.244130Z"
+        }],
+        "users": []
+    }"#;
+
+    let response = app.restore(body.into(), "truncate=true").await;
+    let authors = sqlx::query!("SELECT name FROM authors")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved authors.");
+
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(authors.len(), 1);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn set_featured_books_replaces_previous_ranking() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await;
+    let mut book_ids = Vec::new();
+    for title in ["Dune", "Dune Messiah", "Children of Dune"] {
+        let body = app
+            .create_book(format!(
+                r#"{{"title":"{}", "author":"Frank Herbert", "genre": "Sci-Fi"}}"#,
+                title
+            ))
+            .await
+            .json::<Value>()
+            .await
+            .expect("Failed to deserialize response body.");
+        book_ids.push(body["id"].clone());
+    }
+
+    app.set_featured_books(json!([book_ids[0], book_ids[1]]))
+        .await;
+    let response = app
+        .set_featured_books(json!([book_ids[2], book_ids[0]]))
+        .await;
+    let featured = app
+        .featured_books()
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(featured.as_array().unwrap().len(), 2);
+    assert_eq!(featured[0]["title"], "Children of Dune");
+    assert_eq!(featured[0]["featured_rank"], 1);
+    assert_eq!(featured[1]["title"], "Dune");
+    assert_eq!(featured[1]["featured_rank"], 2);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn set_featured_books_with_unknown_book_changes_nothing() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await;
+    let body = app
+        .create_book(r#"{"title":"Dune", "author":"Frank Herbert", "genre": "Sci-Fi"}"#.into())
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    app.set_featured_books(json!([body["id"]])).await;
+
+    let unknown = app
+        .set_featured_books(json!(["a56de2a8-61d3-43f4-b66b-b454c2b54589"]))
+        .await;
+    let duplicated = app
+        .set_featured_books(json!([body["id"], body["id"]]))
+        .await;
+    let featured = app
+        .featured_books()
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(unknown.status().as_u16(), 400);
+    assert_eq!(duplicated.status().as_u16(), 400);
+    assert_eq!(featured[0]["title"], "Dune");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn admin_routes_reject_unauthenticated_requests() {
+    let app = spawn_app().await;
+
+    let backup = app.get("/admin/backup").await;
+    let encoded_backup = app.get("/%61dmin/backup").await;
+    // The router decodes the path, so this reaches the real backup handler.
+    let encoded_with_token = reqwest::Client::new()
+        .get(format!("http://{}/%61dmin/backup", &app.address))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let encoded_maintenance = reqwest::Client::new()
+        .post(format!("http://{}/%61dmin/maintenance", &app.address))
+        .json(&json!({"enabled": true}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let maintenance = reqwest::Client::new()
+        .post(format!("http://{}/admin/maintenance", &app.address))
+        .bearer_auth("not-the-token")
+        .json(&json!({"enabled": true}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(backup.status().as_u16(), 401);
+    assert_eq!(encoded_backup.status().as_u16(), 401);
+    assert_eq!(encoded_maintenance.status().as_u16(), 401);
+    assert_eq!(encoded_with_token.status().as_u16(), 200);
+    assert_eq!(maintenance.status().as_u16(), 401);
+    let body = backup
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    assert_eq!(body["error"]["code"], "unauthorized");
+    assert_eq!(
+        app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+            .await
+            .status()
+            .as_u16(),
+        201
+    );
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn admin_routes_are_closed_without_a_configured_token() {
+    let app = spawn_app_with(|config| config.admin_token = None).await;
+
+    let response = app.backup().await;
+
+    assert_eq!(response.status().as_u16(), 401);
+
+    drop_db(app.db_name, app.db_url).await;
+}
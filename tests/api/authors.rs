@@ -1,4 +1,6 @@
-use crate::test_helpers::{drop_db, spawn_app};
+use crate::test_helpers::{drop_db, spawn_app, spawn_app_with};
+use midnight_library::db::TimedPool;
+use midnight_library::middleware::purge_expired_keys;
 use serde_json::Value;
 
 #[tokio::test]
@@ -23,45 +25,1318 @@ async fn authors_index() {
     drop_db(app.db_name, app.db_url).await;
 }
 
+#[tokio::test]
+async fn authors_index_with_timezone() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+
+    let response = app.author_index_with_query("tz=Asia/Kolkata").await;
+    let parsed_response = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    let created_at = parsed_response[0]["created_at"]
+        .as_str()
+        .expect("Failed to extract created_at from response.");
+    assert!(created_at.ends_with("+05:30"));
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_search_and_export_honour_the_timezone() {
+    let app = spawn_app().await;
+    let author_id = author_id_from(
+        app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+            .await,
+    )
+    .await;
+
+    let search = app
+        .search_authors("q=tolkien&tz=Asia/Kolkata")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let export = app
+        .get(&format!("/authors/{}/export?tz=Asia/Kolkata", author_id))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert!(search[0]["created_at"]
+        .as_str()
+        .unwrap()
+        .ends_with("+05:30"));
+    assert!(export["created_at"].as_str().unwrap().ends_with("+05:30"));
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn authors_index_with_unknown_timezone() {
+    let app = spawn_app().await;
+
+    let response = app.author_index_with_query("tz=Mars/Olympus_Mons").await;
+
+    assert_eq!(response.status().as_u16(), 400);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn show_author_with_latest_book() {
+    let app = spawn_app().await;
+    let author_id = author_id_from(
+        app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+            .await,
+    )
+    .await;
+    app.create_book(r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction"}"#.into())
+        .await;
+    app.create_book(
+        r#"{"title":"Lord of the Rings", "author":"JRR Tolkien", "genre": "Fiction"}"#.into(),
+    )
+    .await;
+
+    let response = app
+        .show_author(format!("{}?include=latest_book", author_id))
+        .await;
+    let response_body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(response_body["name"], "JRR Tolkien");
+    assert_eq!(response_body["latest_book"]["title"], "Lord of the Rings");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn show_author_with_latest_book_without_books() {
+    let app = spawn_app().await;
+    let author_id = author_id_from(
+        app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+            .await,
+    )
+    .await;
+
+    let response = app
+        .show_author(format!("{}?include=latest_book", author_id))
+        .await;
+    let response_body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert!(response_body["latest_book"].is_null());
+    assert!(response_body.get("latest_book").is_some());
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_exists_is_case_insensitive() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+
+    let existing = app
+        .author_exists("jrr tolkien")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let missing = app
+        .author_exists("Herman Melville")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(existing["exists"], true);
+    assert_eq!(missing["exists"], false);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn authors_index_sorting() {
+    let app = spawn_app_with(|config| config.default_sort.authors = String::from("name")).await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    app.create_author(r#"{"name":"Herman Melville", "nationality":"American"}"#.into())
+        .await;
+
+    let by_default = app
+        .author_index()
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let descending = app
+        .author_index_with_query("sort=-nationality")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let unknown = app.author_index_with_query("sort=price").await;
+
+    assert_eq!(by_default[0]["name"], "Herman Melville");
+    assert_eq!(descending[0]["nationality"], "British");
+    assert_eq!(unknown.status().as_u16(), 400);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn authors_index_pagination_counts_filtered_rows() {
+    let app = spawn_app().await;
+    for name in ["JRR Tolkien", "CS Lewis", "Herman Melville"] {
+        app.create_author(format!(r#"{{"name":"{}", "nationality":"British"}}"#, name))
+            .await;
+    }
+    app.create_book(
+        r#"{"title":"Moby Dick", "author":"Herman Melville", "genre": "Adventure"}"#.into(),
+    )
+    .await;
+
+    let response = app
+        .author_index_with_query("has_books=false&sort=name&per_page=1")
+        .await;
+    let total = response.headers()["X-Total-Count"].clone();
+    let link = response.headers()["Link"].clone();
+    let authors = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(total, "2");
+    assert_eq!(
+        link,
+        "</authors?has_books=false&sort=name&per_page=1&page=2>; rel=\"next\""
+    );
+    assert_eq!(authors.as_array().unwrap().len(), 1);
+    assert_eq!(authors[0]["name"], "CS Lewis");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn authors_index_has_books_filter() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    app.create_author(r#"{"name":"Herman Melville", "nationality":"American"}"#.into())
+        .await;
+    app.create_book(
+        r#"{"title":"Moby Dick", "author":"Herman Melville", "genre": "Adventure"}"#.into(),
+    )
+    .await;
+
+    let without_books = app
+        .author_index_with_query("has_books=false")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let with_books = app
+        .author_index_with_query("has_books=true&sort=name")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let invalid = app.author_index_with_query("has_books=maybe").await;
+
+    assert_eq!(without_books.as_array().unwrap().len(), 1);
+    assert_eq!(without_books[0]["name"], "JRR Tolkien");
+    assert_eq!(with_books.as_array().unwrap().len(), 1);
+    assert_eq!(with_books[0]["name"], "Herman Melville");
+    assert_eq!(invalid.status().as_u16(), 400);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_by_name() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+
+    let found = app.author_by_name("jrr tolkien").await;
+    let missing = app.author_by_name("Herman Melville").await;
+
+    assert_eq!(found.status().as_u16(), 200);
+    let parsed_response = found
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    assert_eq!(parsed_response["name"], "JRR Tolkien");
+    assert_eq!(missing.status().as_u16(), 404);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_by_name_lists_candidates_when_ambiguous() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"John Smith", "nationality":"British"}"#.into())
+        .await;
+    app.create_author(r#"{"name":"John Smith", "nationality":"American"}"#.into())
+        .await;
+
+    let response = app.author_by_name("John Smith").await;
+
+    assert_eq!(response.status().as_u16(), 300);
+    let parsed_response = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    assert_eq!(parsed_response["author_ids"].as_array().unwrap().len(), 2);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn batch_authors_preserves_request_order() {
+    let app = spawn_app().await;
+    let tolkien_id = author_id_from(
+        app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+            .await,
+    )
+    .await;
+    let herbert_id = author_id_from(
+        app.create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+            .await,
+    )
+    .await;
+    app.create_book(r#"{"title":"Dune", "author":"Frank Herbert", "genre": "Sci-Fi"}"#.into())
+        .await;
+
+    let response = app
+        .batch_authors(format!(
+            r#"["{}", "{}", "{}"]"#,
+            herbert_id,
+            uuid::Uuid::new_v4(),
+            tolkien_id
+        ))
+        .await;
+    let parsed_response = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(parsed_response.as_array().unwrap().len(), 2);
+    assert_eq!(parsed_response[0]["name"], "Frank Herbert");
+    assert_eq!(parsed_response[0]["books"][0]["title"], "Dune");
+    assert_eq!(parsed_response[1]["name"], "JRR Tolkien");
+    assert_eq!(parsed_response[1]["books"].as_array().unwrap().len(), 0);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn search_authors() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    app.create_author(r#"{"name":"Herman Melville", "nationality":"American"}"#.into())
+        .await;
+
+    let response = app.search_authors("q=tolk").await;
+    let parsed_response = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(parsed_response.as_array().unwrap().len(), 1);
+    assert_eq!(parsed_response[0]["name"], "JRR Tolkien");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn fuzzy_search_authors_tolerates_typos() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    app.create_author(r#"{"name":"Herman Melville", "nationality":"American"}"#.into())
+        .await;
+
+    let exact_response = app.search_authors("q=Tolkein").await;
+    let exact_results = exact_response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let fuzzy_response = app.search_authors("q=Tolkein&fuzzy=true").await;
+    let fuzzy_results = fuzzy_response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(exact_results.as_array().unwrap().len(), 0);
+    assert_eq!(fuzzy_results.as_array().unwrap().len(), 1);
+    assert_eq!(fuzzy_results[0]["name"], "JRR Tolkien");
+    assert!(fuzzy_results[0]["similarity"].as_f64().unwrap() > 0.3);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
 #[tokio::test]
 async fn show_author() {
     let app = spawn_app().await;
-    let create_response = app
+    let create_response = app
+        .create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    let create_status = create_response.status().as_u16();
+    let location = create_response.headers()["Location"].clone();
+    let response_body = create_response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let author_id = response_body["id"]
+        .as_str()
+        .expect("Failed to extract author id from response.");
+
+    let response = app.show_author(author_id.into()).await;
+    let response_body2 = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(response_body2["name"], "JRR Tolkien");
+    assert_eq!(response_body2["nationality"], "British");
+    assert_eq!(response_body2["id"], author_id);
+    assert_eq!(create_status, 201);
+    assert_eq!(location, format!("/authors/{}", author_id).as_str());
+    assert_eq!(response_body["name"], "JRR Tolkien");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_books_are_paginated() {
+    let app = spawn_app().await;
+    let create_response = app
+        .create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    let response_body = create_response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let author_id = response_body["id"]
+        .as_str()
+        .expect("Failed to extract author id from response.");
+    for title in ["The Hobbit", "The Fellowship of the Ring", "The Two Towers"] {
+        app.create_book(format!(
+            r#"{{"title":"{}", "author":"JRR Tolkien", "genre":"Fantasy"}}"#,
+            title
+        ))
+        .await;
+    }
+
+    let second_page = app.author_books(author_id, "page=2&per_page=2").await;
+    let second_page_headers = second_page.headers().clone();
+    let second_page = second_page
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let past_the_end = app.author_books(author_id, "page=3&per_page=2").await;
+    let past_the_end_total = past_the_end.headers()["x-total-count"].clone();
+    let past_the_end = past_the_end
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(second_page_headers["x-total-count"], "3");
+    assert_eq!(
+        second_page_headers["link"],
+        format!(
+            "</authors/{}/books?per_page=2&page=1>; rel=\"prev\"",
+            author_id
+        )
+    );
+    assert_eq!(second_page.as_array().unwrap().len(), 1);
+    assert_eq!(second_page[0]["title"], "The Two Towers");
+    assert_eq!(past_the_end_total, "3");
+    assert_eq!(past_the_end, serde_json::json!([]));
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_books_for_missing_author() {
+    let app = spawn_app().await;
+
+    let response = app
+        .author_books("7c7b6f3e-1b1f-4a3e-9a3b-2f2d3b3c4d5e", "")
+        .await;
+
+    assert_eq!(response.status().as_u16(), 404);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn similar_authors_ranked_by_shared_genres() {
+    let app = spawn_app().await;
+    let create_response = app
+        .create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    let response_body = create_response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let author_id = response_body["id"]
+        .as_str()
+        .expect("Failed to extract author id from response.");
+    for author in ["CS Lewis", "Ursula K Le Guin", "Agatha Christie"] {
+        app.create_author(format!(
+            r#"{{"name":"{}", "nationality":"British"}}"#,
+            author
+        ))
+        .await;
+    }
+    for (title, author, genre) in [
+        ("The Hobbit", "JRR Tolkien", "Fantasy"),
+        ("The Fall of Arthur", "JRR Tolkien", "Poetry"),
+        ("Narnia", "CS Lewis", "Fantasy"),
+        ("Spirits in Bondage", "CS Lewis", "Poetry"),
+        ("Earthsea", "Ursula K Le Guin", "Fantasy"),
+        ("Poirot", "Agatha Christie", "Mystery"),
+    ] {
+        app.create_book(format!(
+            r#"{{"title":"{}", "author":"{}", "genre":"{}"}}"#,
+            title, author, genre
+        ))
+        .await;
+    }
+
+    let response = app.similar_authors(author_id).await;
+    let authors = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(authors.as_array().unwrap().len(), 2);
+    assert_eq!(authors[0]["name"], "CS Lewis");
+    assert_eq!(authors[0]["shared_genres_count"], 2);
+    assert_eq!(authors[0]["shared_genres"][0], "Fantasy");
+    assert_eq!(authors[1]["name"], "Ursula K Le Guin");
+    assert_eq!(authors[1]["shared_genres_count"], 1);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn similar_authors_for_missing_author() {
+    let app = spawn_app().await;
+
+    let response = app
+        .similar_authors("7c7b6f3e-1b1f-4a3e-9a3b-2f2d3b3c4d5e")
+        .await;
+
+    assert_eq!(response.status().as_u16(), 404);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn export_author() {
+    let app = spawn_app().await;
+    let create_response = app
+        .create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    let response_body = create_response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let author_id = response_body["id"]
+        .as_str()
+        .expect("Failed to extract author id from response.");
+    app.create_book(
+        r#"{"title":"Lord of the Rings", "author":"JRR Tolkien", "genre": "Fiction"}"#.into(),
+    )
+    .await;
+    app.create_book(r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction"}"#.into())
+        .await;
+
+    let response = app.export_author(author_id, "").await;
+    let document = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(document["id"], author_id);
+    assert_eq!(document["name"], "JRR Tolkien");
+    assert_eq!(document["books"][0]["title"], "Lord of the Rings");
+    assert_eq!(document["books"][1]["title"], "The Hobbit");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn export_author_as_csv() {
+    let app = spawn_app().await;
+    let create_response = app
+        .create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    let response_body = create_response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let author_id = response_body["id"]
+        .as_str()
+        .expect("Failed to extract author id from response.");
+    app.create_book(r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction"}"#.into())
+        .await;
+
+    let response = app.export_author(author_id, "format=csv").await;
+    let body = response
+        .text()
+        .await
+        .expect("Failed to read response body.");
+    let lines: Vec<&str> = body.lines().collect();
+
+    assert_eq!(lines.len(), 2);
+    assert!(lines[1].starts_with(&format!("{},JRR Tolkien,British,", author_id)));
+    assert!(lines[1].contains(",The Hobbit,Fiction,"));
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn export_missing_author() {
+    let app = spawn_app().await;
+
+    let response = app
+        .export_author("e457c912-5a04-4bfc-abeb-5a0e2fe91a72", "")
+        .await;
+
+    assert_eq!(response.status().as_u16(), 404);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_creation() {
+    let app = spawn_app().await;
+    let body = r#"{"name":"JRR Tolkien", "nationality":"British"}"#;
+
+    let response = app.create_author(body.into()).await;
+    let record = sqlx::query!("SELECT * FROM authors")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved author.");
+
+    assert!(response.status().is_success());
+    assert_eq!(record.name, "JRR Tolkien");
+    assert_eq!(record.nationality, "British");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_creation_with_incomplete_data() {
+    let app = spawn_app().await;
+    let body = r#"{"name":"JRR Tolkien"}"#;
+
+    let response = app.create_author(body.into()).await;
+    let record = sqlx::query!("SELECT * FROM authors")
+        .fetch_optional(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved author.");
+
+    assert!(response.status().is_client_error());
+    assert!(record.is_none());
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn bulk_author_creation() {
+    let app = spawn_app().await;
+    let body = r#"[
+        {"name":"JRR Tolkien", "nationality":"British"},
+        {"name":"Herman Melville", "nationality":"American"}
+    ]"#;
+
+    let response = app
+        .bulk_create_authors(body.into(), "tz=America/Sao_Paulo")
+        .await;
+    let status = response.status().as_u16();
+    let response_body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let records = sqlx::query!("SELECT id FROM authors WHERE name = 'Herman Melville'")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved authors.");
+
+    assert_eq!(status, 201);
+    assert_eq!(records.len(), 1);
+    let created = &response_body["created"][1];
+    assert_eq!(created["index"], 1);
+    assert_eq!(created["id"], records[0].id.to_string());
+    assert_eq!(created["name"], "Herman Melville");
+    assert_eq!(created["nationality"], "American");
+    assert!(created["created_at"].as_str().unwrap().ends_with("-03:00"));
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn bulk_author_creation_as_csv() {
+    let app = spawn_app().await;
+    let body = r#"[
+        {"name":"JRR Tolkien", "nationality":"British"},
+        {"name":"Melville, Herman", "nationality":"American"}
+    ]"#;
+
+    let response = app.bulk_create_authors_as_csv(body.into()).await;
+    let status = response.status().as_u16();
+    let content_type = response.headers()["content-type"].clone();
+    let csv = response
+        .text()
+        .await
+        .expect("Failed to read response body.");
+    let records = sqlx::query!("SELECT id, name FROM authors")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved authors.");
+    let id_of = |name: &str| {
+        records
+            .iter()
+            .find(|record| record.name == name)
+            .expect("Author was not saved.")
+            .id
+    };
+
+    assert_eq!(status, 201);
+    assert_eq!(content_type, "text/csv; charset=utf-8");
+    assert_eq!(
+        csv,
+        format!(
+            "id,name,nationality\n{},JRR Tolkien,British\n{},\"Melville, Herman\",American\n",
+            id_of("JRR Tolkien"),
+            id_of("Melville, Herman")
+        )
+    );
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn bulk_author_creation_strict_mode_rejects_whole_batch() {
+    let app = spawn_app().await;
+    let body = r#"[
+        {"name":"JRR Tolkien", "nationality":"British"},
+        {"name":"", "nationality":"American"},
+        {"name":"Machado de Assis"}
+    ]"#;
+
+    let response = app.bulk_create_authors(body.into(), "").await;
+    let status = response.status().as_u16();
+    let response_body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let record = sqlx::query!("SELECT * FROM authors")
+        .fetch_optional(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved author.");
+
+    assert_eq!(status, 422);
+    assert_eq!(response_body["errors"][0]["index"], 1);
+    assert_eq!(response_body["errors"][0]["code"], "invalid_author_name");
+    assert_eq!(response_body["errors"][0]["field"], "name");
+    assert_eq!(response_body["errors"][1]["index"], 2);
+    assert_eq!(response_body["errors"][1]["code"], "malformed_author");
+    assert!(record.is_none());
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn bulk_author_creation_partial_mode() {
+    let app = spawn_app().await;
+    let body = r#"[
+        {"name":"JRR Tolkien", "nationality":"British"},
+        {"name":"Herman Melville", "nationality":"American"},
+        {"name":"", "nationality":"Brazilian"}
+    ]"#;
+
+    let response = app.bulk_create_authors(body.into(), "partial=true").await;
+    let status = response.status().as_u16();
+    let response_body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let records = sqlx::query!("SELECT * FROM authors")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved authors.");
+
+    assert_eq!(status, 207);
+    assert_eq!(response_body["created"].as_array().unwrap().len(), 2);
+    assert_eq!(response_body["created"][0]["name"], "JRR Tolkien");
+    assert_eq!(response_body["errors"][0]["index"], 2);
+    assert_eq!(records.len(), 2);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn nationality_stats() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    app.create_author(r#"{"name":"Jane Austen", "nationality":"British"}"#.into())
+        .await;
+    app.create_author(r#"{"name":"Herman Melville", "nationality":"American"}"#.into())
+        .await;
+
+    let response = app.nationality_stats().await;
+    let parsed_response = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(parsed_response[0]["nationality"], "British");
+    assert_eq!(parsed_response[0]["authors_count"], 2);
+    assert_eq!(parsed_response[1]["nationality"], "American");
+    assert_eq!(parsed_response[1]["authors_count"], 1);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_book_counts() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    app.create_author(r#"{"name":"Herman Melville", "nationality":"American"}"#.into())
+        .await;
+    app.create_book(r#"{"title":"Dune", "author":"Frank Herbert", "genre": "Sci-Fi"}"#.into())
+        .await;
+    app.create_book(
+        r#"{"title":"Dune Messiah", "author":"Frank Herbert", "genre": "Sci-Fi"}"#.into(),
+    )
+    .await;
+    app.create_book(r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fantasy"}"#.into())
+        .await;
+
+    let counts = app
+        .author_book_counts("")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let with_empty = app
+        .author_book_counts("include_empty=true&limit=2&offset=1")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(counts.as_array().unwrap().len(), 2);
+    assert_eq!(counts[0]["name"], "Frank Herbert");
+    assert_eq!(counts[0]["books_count"], 2);
+    assert_eq!(counts[1]["books_count"], 1);
+    assert_eq!(with_empty.as_array().unwrap().len(), 2);
+    assert_eq!(with_empty[1]["name"], "Herman Melville");
+    assert_eq!(with_empty[1]["books_count"], 0);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_creation_at_name_length_boundary() {
+    let app = spawn_app().await;
+    let longest_name = "a".repeat(256);
+    let too_long_name = "a".repeat(257);
+
+    let accepted = app
+        .create_author(format!(
+            r#"{{"name":"{}", "nationality":"British"}}"#,
+            longest_name
+        ))
+        .await;
+    let rejected = app
+        .create_author(format!(
+            r#"{{"name":"{}", "nationality":"British"}}"#,
+            too_long_name
+        ))
+        .await;
+    let direct_insert = sqlx::query!(
+        "INSERT INTO authors (name, nationality, created_at) VALUES ($1, 'British', now())",
+        too_long_name
+    )
+    .execute(&app.db_pool)
+    .await;
+
+    assert_eq!(accepted.status().as_u16(), 201);
+    assert_eq!(rejected.status().as_u16(), 422);
+    assert!(
+        direct_insert.is_err(),
+        "Database accepted an oversized name."
+    );
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_creation_error_is_a_json_envelope() {
+    let app = spawn_app().await;
+    let body = r#"{"name":"   ", "nationality":"Française"}"#;
+
+    let response = app.create_author(body.into()).await;
+
+    assert_eq!(response.status().as_u16(), 422);
+    assert_eq!(response.headers()["Content-Type"], "application/json");
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    assert_eq!(body["error"]["code"], "invalid_input");
+    assert_eq!(body["error"]["details"][0]["field"], "name");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_creation_error_follows_accept_language() {
+    let app = spawn_app().await;
+    let body = r#"{"name":"", "nationality":"Brazilian"}"#;
+
+    let portuguese = app.create_author_in(body.into(), "pt-BR, en;q=0.5").await;
+    let unsupported = app.create_author_in(body.into(), "ja").await;
+
+    assert_eq!(portuguese.headers()["Content-Language"], "pt");
+    let portuguese = portuguese
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let unsupported = unsupported
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    assert_eq!(portuguese["error"]["code"], "invalid_input");
+    assert_eq!(
+        portuguese["error"]["message"],
+        "'' não é um nome de autor válido."
+    );
+    assert_eq!(
+        portuguese["error"]["details"][0]["code"],
+        "invalid_author_name"
+    );
+    assert_eq!(
+        unsupported["error"]["message"],
+        "'' is not a valid author name."
+    );
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn transfer_books_between_authors() {
+    let app = spawn_app().await;
+    let source_id = author_id_from(
+        app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+            .await,
+    )
+    .await;
+    let target_id = author_id_from(
+        app.create_author(r#"{"name":"Christopher Tolkien", "nationality":"British"}"#.into())
+            .await,
+    )
+    .await;
+    app.create_book(
+        r#"{"title":"Lord of the Rings", "author":"JRR Tolkien", "genre": "Fiction"}"#.into(),
+    )
+    .await;
+    app.create_book(r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction"}"#.into())
+        .await;
+
+    let response = app
+        .transfer_books(&source_id, format!(r#"{{"target_id": "{}"}}"#, target_id))
+        .await;
+    let status = response.status().as_u16();
+    let response_body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let authors = sqlx::query!("SELECT id FROM authors")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved authors.");
+    let transferred_books = sqlx::query!(
+        "SELECT id FROM books WHERE author_id = $1",
+        uuid::Uuid::parse_str(&target_id).unwrap()
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .expect("Failed to fetch saved books.");
+
+    assert_eq!(status, 200);
+    assert_eq!(response_body["books_moved"], 2);
+    assert_eq!(authors.len(), 2);
+    assert_eq!(transferred_books.len(), 2);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn transfer_books_onto_a_title_the_target_already_has() {
+    let app = spawn_app().await;
+    let source_id = author_id_from(
+        app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+            .await,
+    )
+    .await;
+    let target_id = author_id_from(
+        app.create_author(r#"{"name":"Christopher Tolkien", "nationality":"British"}"#.into())
+            .await,
+    )
+    .await;
+    app.create_book(r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction"}"#.into())
+        .await;
+    app.create_book(
+        r#"{"title":"The Hobbit", "author":"Christopher Tolkien", "genre": "Fiction"}"#.into(),
+    )
+    .await;
+
+    let response = app
+        .transfer_books(&source_id, format!(r#"{{"target_id": "{}"}}"#, target_id))
+        .await;
+    let status = response.status().as_u16();
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let source_books = sqlx::query!(
+        "SELECT id FROM books WHERE author_id = $1",
+        uuid::Uuid::parse_str(&source_id).unwrap()
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .expect("Failed to fetch saved books.");
+
+    assert_eq!(status, 409);
+    assert_eq!(body["error"]["code"], "conflict");
+    assert_eq!(source_books.len(), 1);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn transfer_books_to_self() {
+    let app = spawn_app().await;
+    let author_id = author_id_from(
+        app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+            .await,
+    )
+    .await;
+
+    let response = app
+        .transfer_books(&author_id, format!(r#"{{"target_id": "{}"}}"#, author_id))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 400);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn transfer_books_to_missing_author() {
+    let app = spawn_app().await;
+    let author_id = author_id_from(
+        app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+            .await,
+    )
+    .await;
+
+    let response = app
+        .transfer_books(
+            &author_id,
+            r#"{"target_id": "e457c912-5a04-4bfc-abeb-5a0e2fe91a72"}"#.into(),
+        )
+        .await;
+
+    assert_eq!(response.status().as_u16(), 404);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn transfer_books_to_malformed_author_id() {
+    let app = spawn_app().await;
+    let author_id = author_id_from(
+        app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+            .await,
+    )
+    .await;
+
+    let response = app
+        .transfer_books(&author_id, r#"{"target_id": "not-a-uuid"}"#.into())
+        .await;
+    let status = response.status().as_u16();
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(status, 400);
+    assert_eq!(body["error"]["code"], "invalid_uuid");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_deletion() {
+    let app = spawn_app().await;
+    let response = app
         .create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
         .await;
-    let response_body = create_response
+    let response_body = response
         .json::<Value>()
         .await
         .expect("Failed to deserialize response body.");
-    let author_id = response_body["author_id"]
+    let author_id = response_body["id"]
         .as_str()
         .expect("Failed to extract author id from response.");
 
-    let response = app.show_author(author_id.into()).await;
-    let response_body2 = response
+    app.delete_author(format!(r#"{{"id": "{}"}}"#, author_id))
+        .await;
+    let record = sqlx::query!("SELECT * FROM authors")
+        .fetch_optional(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved author.");
+
+    assert!(record.is_none(), "Record was not deleted successfully.");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_creation_with_a_repeated_idempotency_key() {
+    let app = spawn_app().await;
+    let body = r#"{"name":"JRR Tolkien", "nationality":"British"}"#;
+
+    let first = app.create_author_with_key(body, "retry-1").await;
+    let first_status = first.status().as_u16();
+    let first_body = first.text().await.expect("Failed to read response body.");
+    let retry = app.create_author_with_key(body, "retry-1").await;
+    let reused = app
+        .create_author_with_key(
+            r#"{"name":"Ursula K. Le Guin", "nationality":"American"}"#,
+            "retry-1",
+        )
+        .await;
+    let count = sqlx::query_scalar!("SELECT count(*) FROM authors")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count authors.");
+
+    assert_eq!(first_status, 201);
+    assert_eq!(retry.status().as_u16(), 201);
+    assert_eq!(retry.headers()["Idempotent-Replayed"], "true");
+    assert_eq!(retry.text().await.unwrap(), first_body);
+    assert_eq!(reused.status().as_u16(), 422);
+    assert_eq!(count, Some(1));
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn idempotency_keys_match_the_route_however_it_is_encoded() {
+    let app = spawn_app().await;
+    let body = r#"{"name":"JRR Tolkien", "nationality":"British"}"#;
+
+    app.create_author_with_key(body, "encoded").await;
+    let retry = reqwest::Client::new()
+        .post(format!("http://{}/%61uthors", &app.address))
+        .header("Content-Type", "application/json")
+        .header("Idempotency-Key", "encoded")
+        .body(body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let count = sqlx::query_scalar!("SELECT count(*) FROM authors")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count authors.");
+
+    assert_eq!(retry.status().as_u16(), 201);
+    assert_eq!(retry.headers()["Idempotent-Replayed"], "true");
+    assert_eq!(count, Some(1));
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn idempotency_keys_are_scoped_to_the_route() {
+    let app = spawn_app().await;
+
+    app.create_author_with_key(r#"{"name":"JRR Tolkien", "nationality":"British"}"#, "k")
+        .await;
+    let book = reqwest::Client::new()
+        .post(format!("http://{}/books", &app.address))
+        .header("Content-Type", "application/json")
+        .header("Idempotency-Key", "k")
+        .body(r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fantasy"}"#)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(book.status().as_u16(), 201);
+    assert!(book.headers().get("Idempotent-Replayed").is_none());
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn oversized_bodies_with_an_idempotency_key_are_rejected() {
+    let app = spawn_app().await;
+    let body = format!(
+        r#"{{"name":"{}", "nationality":"British"}}"#,
+        "a".repeat(3 * 1024 * 1024)
+    );
+
+    let response = app.create_author_with_key(&body, "big").await;
+    let status = response.status().as_u16();
+    let response_body = response
         .json::<Value>()
         .await
         .expect("Failed to deserialize response body.");
+    let keys = sqlx::query_scalar!("SELECT count(*) FROM idempotency_keys")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count idempotency keys.");
 
-    assert_eq!(response_body2["name"], "JRR Tolkien");
-    assert_eq!(response_body2["nationality"], "British");
-    assert_eq!(response_body2["id"], author_id);
+    assert_eq!(status, 413);
+    assert_eq!(response_body["error"]["code"], "payload_too_large");
+    assert_eq!(keys, Some(0));
 
     drop_db(app.db_name, app.db_url).await;
 }
 
 #[tokio::test]
-async fn author_creation() {
+async fn expired_idempotency_keys_are_purged() {
     let app = spawn_app().await;
-    let body = r#"{"name":"JRR Tolkien", "nationality":"British"}"#;
+    app.create_author_with_key(
+        r#"{"name":"JRR Tolkien", "nationality":"British"}"#,
+        "fresh",
+    )
+    .await;
+    app.create_author_with_key(
+        r#"{"name":"Herman Melville", "nationality":"American"}"#,
+        "old",
+    )
+    .await;
+    sqlx::query!(
+        "UPDATE idempotency_keys SET created_at = now() - interval '2 days' WHERE key = 'old'"
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to age idempotency key.");
 
-    let response = app.create_author(body.into()).await;
-    let record = sqlx::query!("SELECT * FROM authors")
+    let purged = purge_expired_keys(&TimedPool::new(app.db_pool.clone()))
+        .await
+        .expect("Failed to purge idempotency keys.");
+    let keys = sqlx::query_scalar!("SELECT key FROM idempotency_keys")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("Failed to fetch idempotency keys.");
+
+    assert_eq!(purged, 1);
+    assert_eq!(keys, vec!["fresh".to_string()]);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn legacy_author_deletion_is_deprecated() {
+    let app = spawn_app().await;
+    let author_id = author_id_from(
+        app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+            .await,
+    )
+    .await;
+
+    let response = app
+        .delete_author(format!(r#"{{"id": "{}"}}"#, author_id))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["Deprecation"], "true");
+    assert!(response.headers().contains_key("Sunset"));
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_deletion_by_id() {
+    let app = spawn_app().await;
+    let author_id = author_id_from(
+        app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+            .await,
+    )
+    .await;
+
+    let response = app.delete_author_by_id(&author_id).await;
+    let missing = app.delete_author_by_id(&author_id).await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(!response.headers().contains_key("Deprecation"));
+    assert_eq!(missing.status().as_u16(), 404);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+async fn author_id_from(response: reqwest::Response) -> String {
+    let response_body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    response_body["id"]
+        .as_str()
+        .expect("Failed to extract author id from response.")
+        .to_string()
+}
+
+#[tokio::test]
+async fn author_partial_update() {
+    let app = spawn_app().await;
+    let author_id = author_id_from(
+        app.create_author(r#"{"name":"JRR Tolkien", "nationality":"English"}"#.into())
+            .await,
+    )
+    .await;
+
+    let response = app
+        .update_author(&author_id, serde_json::json!({"nationality": "British"}))
+        .await;
+    let status = response.status().as_u16();
+    let author = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let record = sqlx::query!("SELECT name, nationality FROM authors")
         .fetch_one(&app.db_pool)
         .await
         .expect("Failed to fetch saved author.");
 
-    assert!(response.status().is_success());
+    assert_eq!(status, 200);
+    assert_eq!(author["name"], "JRR Tolkien");
+    assert_eq!(author["nationality"], "British");
     assert_eq!(record.name, "JRR Tolkien");
     assert_eq!(record.nationality, "British");
 
@@ -69,44 +1344,317 @@ async fn author_creation() {
 }
 
 #[tokio::test]
-async fn author_creation_with_incomplete_data() {
+async fn author_update_rejects_invalid_or_missing_input() {
     let app = spawn_app().await;
-    let body = r#"{"name":"JRR Tolkien"}"#;
+    let author_id = author_id_from(
+        app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+            .await,
+    )
+    .await;
 
-    let response = app.create_author(body.into()).await;
-    let record = sqlx::query!("SELECT * FROM authors")
+    let invalid = app
+        .update_author(&author_id, serde_json::json!({"name": " "}))
+        .await;
+    let empty = app.update_author(&author_id, serde_json::json!({})).await;
+    let missing = app
+        .update_author(
+            "7c7b6f3e-1b1f-4a3e-9a3b-2f2d3b3c4d5e",
+            serde_json::json!({"name": "CS Lewis"}),
+        )
+        .await;
+    let record = sqlx::query!("SELECT name FROM authors")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved author.");
+
+    assert_eq!(invalid.status().as_u16(), 422);
+    assert_eq!(empty.status().as_u16(), 400);
+    assert_eq!(missing.status().as_u16(), 404);
+    assert_eq!(record.name, "JRR Tolkien");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_routes_reject_invalid_uuid() {
+    let app = spawn_app().await;
+
+    let show = app.show_author("42".into()).await;
+    let delete = app.delete_author_by_id("42").await;
+    let legacy_delete = app.delete_author(r#"{"id": "42"}"#.into()).await;
+    let batch = app.batch_authors(r#"["42"]"#.into()).await;
+
+    for response in [show, delete, legacy_delete, batch] {
+        assert_eq!(response.status().as_u16(), 400);
+        let body = response
+            .json::<Value>()
+            .await
+            .expect("Failed to deserialize response body.");
+        assert_eq!(body["error"]["code"], "invalid_uuid");
+    }
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn bulk_create_authors_over_limit() {
+    let app = spawn_app_with(|config| config.bulk_limits.authors_bulk_create = 2).await;
+    let body = r#"[
+        {"name":"JRR Tolkien", "nationality":"British"},
+        {"name":"Herman Melville", "nationality":"American"},
+        {"name":"Machado de Assis", "nationality":"Brazilian"}
+    ]"#;
+
+    let response = app.bulk_create_authors(body.into(), "partial=true").await;
+    let record = sqlx::query!("SELECT id FROM authors")
         .fetch_optional(&app.db_pool)
         .await
         .expect("Failed to fetch saved author.");
 
-    assert!(response.status().is_client_error());
+    assert_eq!(response.status().as_u16(), 400);
     assert!(record.is_none());
 
     drop_db(app.db_name, app.db_url).await;
 }
 
 #[tokio::test]
-async fn author_deletion() {
+async fn batch_authors_over_limit() {
+    let app = spawn_app_with(|config| config.bulk_limits.authors_batch = 2).await;
+
+    let response = app
+        .batch_authors(format!(
+            r#"["{}", "{}", "{}"]"#,
+            uuid::Uuid::new_v4(),
+            uuid::Uuid::new_v4(),
+            uuid::Uuid::new_v4()
+        ))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 400);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn unknown_fields_are_ignored_by_default() {
     let app = spawn_app().await;
+
     let response = app
-        .create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .create_author(
+            r#"{"name":"JRR Tolkien", "nationality":"British", "nationalty":"English"}"#.into(),
+        )
         .await;
-    let response_body = response
+
+    assert_eq!(response.status().as_u16(), 201);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn strict_json_rejects_unknown_fields() {
+    let app = spawn_app_with(|config| config.strict_json = true).await;
+
+    let response = app
+        .create_author(
+            r#"{"name":"JRR Tolkien", "nationality":"British", "nationalty":"English"}"#.into(),
+        )
+        .await;
+    let status = response.status().as_u16();
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let bulk_response = app
+        .bulk_create_authors(
+            r#"[{"name":"JRR Tolkien", "nationality":"British", "nationalty":"English"}]"#.into(),
+            "",
+        )
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let record = sqlx::query!("SELECT id FROM authors")
+        .fetch_optional(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved author.");
+
+    assert_eq!(status, 400);
+    assert_eq!(body["error"]["message"], "Unknown field 'nationalty'.");
+    assert_eq!(bulk_response["errors"][0]["code"], "malformed_author");
+    assert!(record.is_none());
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_aliases_show_up_in_show_and_search() {
+    let app = spawn_app().await;
+    let author_id = author_id_from(
+        app.create_author(r#"{"name":"Stephen King", "nationality":"American"}"#.into())
+            .await,
+    )
+    .await;
+
+    let added = app
+        .add_author_alias(&author_id, r#"{"alias":"Richard Bachman"}"#.into())
+        .await;
+    let duplicate = app
+        .add_author_alias(&author_id, r#"{"alias":"richard bachman"}"#.into())
+        .await;
+    let shown = app
+        .show_author(author_id.clone())
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let found = app
+        .search_authors("q=bachman")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let fuzzy = app
+        .search_authors("q=Bachmann&fuzzy=true")
+        .await
         .json::<Value>()
         .await
         .expect("Failed to deserialize response body.");
-    let author_id = response_body["author_id"]
+
+    assert_eq!(added.status().as_u16(), 201);
+    assert_eq!(duplicate.status().as_u16(), 409);
+    assert_eq!(shown["aliases"], serde_json::json!(["Richard Bachman"]));
+    assert_eq!(found[0]["name"], "Stephen King");
+    assert_eq!(fuzzy[0]["name"], "Stephen King");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn remove_author_alias() {
+    let app = spawn_app().await;
+    let author_id = author_id_from(
+        app.create_author(r#"{"name":"Stephen King", "nationality":"American"}"#.into())
+            .await,
+    )
+    .await;
+    let alias = app
+        .add_author_alias(&author_id, r#"{"alias":"Richard Bachman"}"#.into())
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let alias_id = alias["id"].as_str().unwrap();
+
+    let removed = app.remove_author_alias(&author_id, alias_id).await;
+    let removed_again = app.remove_author_alias(&author_id, alias_id).await;
+    let unknown_author = app
+        .add_author_alias(
+            "a56de2a8-61d3-43f4-b66b-b454c2b54589",
+            r#"{"alias":"Anonymous"}"#.into(),
+        )
+        .await;
+    let search = app
+        .search_authors("q=bachman")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert!(removed.status().is_success());
+    assert_eq!(removed_again.status().as_u16(), 404);
+    assert_eq!(unknown_author.status().as_u16(), 404);
+    assert_eq!(search.as_array().unwrap().len(), 0);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn author_index_answers_an_error_envelope_when_the_query_fails() {
+    let app = spawn_app().await;
+    sqlx::query("ALTER TABLE authors RENAME TO authors_elsewhere")
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to rename authors table.");
+
+    let response = app.author_index().await;
+
+    assert_eq!(response.status().as_u16(), 500);
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    assert_eq!(body["error"]["code"], "internal_error");
+    assert!(!body["error"]["message"]
         .as_str()
-        .expect("Failed to extract author id from response.");
+        .unwrap()
+        .contains("authors"));
 
-    app.delete_author(format!(r#"{{"id": "{}"}}"#, author_id))
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn show_missing_author_is_not_found() {
+    let app = spawn_app().await;
+
+    let response = app
+        .show_author("7c7b6f3e-1b1f-4a3e-9a3b-2f2d3b3c4d5e".into())
         .await;
-    let record = sqlx::query!("SELECT * FROM authors")
-        .fetch_optional(&app.db_pool)
+
+    assert_eq!(response.status().as_u16(), 404);
+    let body = response
+        .json::<Value>()
         .await
-        .expect("Failed to fetch saved author.");
+        .expect("Failed to deserialize response body.");
+    assert_eq!(body["error"]["code"], "not_found");
+    assert_eq!(body["error"]["message"], "Author not found");
 
-    assert!(record.is_none(), "Record was not deleted successfully.");
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn deleting_an_author_with_books_needs_cascade() {
+    let app = spawn_app().await;
+    let author_id = author_id_from(
+        app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+            .await,
+    )
+    .await;
+    app.create_book(format!(
+        r#"{{"title":"The Hobbit", "author_id":"{}", "genre": "Fiction"}}"#,
+        author_id
+    ))
+    .await;
+
+    let refused = app.delete_author_by_id(&author_id).await;
+    let refused_status = refused.status().as_u16();
+    let refused_body = refused
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let books_left = sqlx::query!(r#"SELECT COUNT(*) AS "count!" FROM books"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count books.")
+        .count;
+    let cascaded = app
+        .delete_author_by_id_with_query(&author_id, "cascade=true")
+        .await;
+    let cascaded_status = cascaded.status().as_u16();
+    let cascaded_body = cascaded
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let books = sqlx::query!("SELECT id FROM books")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("Failed to fetch books.");
+
+    assert_eq!(refused_status, 409);
+    assert_eq!(refused_body["error"]["code"], "conflict");
+    assert_eq!(books_left, 1);
+    assert_eq!(cascaded_status, 200);
+    assert_eq!(cascaded_body["books_deleted"], 1);
+    assert!(books.is_empty());
 
     drop_db(app.db_name, app.db_url).await;
 }
@@ -1,5 +1,50 @@
 use crate::test_helpers::{drop_db, spawn_app};
+use flate2::read::GzDecoder;
 use serde_json::Value;
+use std::io::Read;
+
+#[tokio::test]
+async fn created_book_is_returned_with_its_location() {
+    let app = spawn_app().await;
+    let author = app
+        .create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    let response = app
+        .create_book(r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fantasy"}"#.into())
+        .await;
+    let status = response.status().as_u16();
+    let location = response.headers()["Location"]
+        .to_str()
+        .expect("Failed to read Location header.")
+        .to_string();
+    let created = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let shown = app
+        .get(&location)
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(status, 201);
+    assert_eq!(
+        location,
+        format!("/books/{}", created["id"].as_str().unwrap())
+    );
+    assert_eq!(created["title"], "The Hobbit");
+    assert_eq!(created["author"], "JRR Tolkien");
+    assert_eq!(created["author_id"], author["id"]);
+    assert_eq!(created["genre"], "Fantasy");
+    assert_eq!(shown["id"], created["id"]);
+
+    drop_db(app.db_name, app.db_url).await;
+}
 
 #[tokio::test]
 async fn books_index() {
@@ -29,6 +74,110 @@ async fn books_index() {
     drop_db(app.db_name, app.db_url).await;
 }
 
+#[tokio::test]
+async fn books_index_is_paginated() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    for title in ["The Hobbit", "The Fellowship of the Ring", "The Two Towers"] {
+        app.create_book(format!(
+            r#"{{"title":"{}", "author":"JRR Tolkien", "genre":"Fantasy"}}"#,
+            title
+        ))
+        .await;
+    }
+
+    let response = app.book_index_with_query("per_page=2&page=2").await;
+    let total = response.headers()["X-Total-Count"].clone();
+    let link = response.headers()["Link"].clone();
+    let books = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let invalid = app.book_index_with_query("page=0").await;
+
+    assert_eq!(total, "3");
+    assert_eq!(link, "</books?per_page=2&page=1>; rel=\"prev\"");
+    assert_eq!(books.as_array().unwrap().len(), 1);
+    assert_eq!(books[0]["title"], "The Two Towers");
+    assert_eq!(invalid.status().as_u16(), 400);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn books_index_as_ndjson() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    app.create_book(
+        r#"{"title":"Lord of the Rings", "author":"JRR Tolkien", "genre": "Fiction"}"#.into(),
+    )
+    .await;
+    app.create_book(r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction"}"#.into())
+        .await;
+
+    let response = app.book_index_as_ndjson().await;
+    assert_eq!(response.headers()["Content-Type"], "application/x-ndjson");
+    let body = response
+        .text()
+        .await
+        .expect("Failed to read response body.");
+    let books: Vec<Value> = body
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Failed to parse NDJSON line."))
+        .collect();
+
+    assert_eq!(books.len(), 2);
+    assert_eq!(books[0]["title"], "Lord of the Rings");
+    assert_eq!(books[1]["title"], "The Hobbit");
+    assert_eq!(books[1]["author"], "JRR Tolkien");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn random_books() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    for title in ["Lord of the Rings", "The Hobbit", "The Silmarillion"] {
+        app.create_book(format!(
+            r#"{{"title":"{}", "author":"JRR Tolkien", "genre": "Fiction"}}"#,
+            title
+        ))
+        .await;
+    }
+
+    let response = app.random_books("count=2").await;
+    let parsed_response = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(parsed_response.as_array().unwrap().len(), 2);
+    assert_eq!(parsed_response[0]["author"], "JRR Tolkien");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn random_books_without_books() {
+    let app = spawn_app().await;
+
+    let response = app.random_books("").await;
+    let status = response.status().as_u16();
+    let parsed_response = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(status, 200);
+    assert_eq!(parsed_response, serde_json::json!([]));
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
 #[tokio::test]
 async fn show_book() {
     let app = spawn_app().await;
@@ -43,7 +192,7 @@ async fn show_book() {
         .json::<Value>()
         .await
         .expect("Failed to deserialize response body.");
-    let book_id = response_body["book_id"]
+    let book_id = response_body["id"]
         .as_str()
         .expect("Failed to extract author id from response.");
 
@@ -61,6 +210,61 @@ async fn show_book() {
     drop_db(app.db_name, app.db_url).await;
 }
 
+#[tokio::test]
+async fn show_book_honors_if_modified_since() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    let response_body = app
+        .create_book(r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction"}"#.into())
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let book_id = response_body["id"]
+        .as_str()
+        .expect("Failed to extract book id from response.");
+
+    let response = app.show_book(book_id.into()).await;
+    let last_modified = response.headers()["Last-Modified"]
+        .to_str()
+        .expect("Last-Modified should be a string.")
+        .to_string();
+    let unchanged = app
+        .show_book_if_modified_since(book_id, &last_modified)
+        .await;
+    let stale = app
+        .show_book_if_modified_since(book_id, "Mon, 01 Jan 2024 00:00:00 GMT")
+        .await;
+
+    assert_eq!(unchanged.status().as_u16(), 304);
+    assert_eq!(stale.status().as_u16(), 200);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn book_updated_at_follows_mutations() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await;
+    app.create_book(r#"{"title":"Dune", "author":"Frank Herbert", "genre": "Sci-Fi"}"#.into())
+        .await;
+
+    sqlx::query!("UPDATE books SET genre = 'Science Fiction'")
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to update book.");
+    let record = sqlx::query!("SELECT created_at, updated_at FROM books")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved book.");
+
+    assert!(record.updated_at > record.created_at);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
 #[tokio::test]
 async fn book_creation() {
     let app = spawn_app().await;
@@ -91,6 +295,191 @@ async fn book_creation() {
     drop_db(app.db_name, app.db_url).await;
 }
 
+#[tokio::test]
+async fn duplicate_book_creation_conflicts() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    app.create_author(r#"{"name":"Christopher Tolkien", "nationality":"British"}"#.into())
+        .await;
+    let original = app
+        .create_book(r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction"}"#.into())
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    let duplicate = app
+        .create_book(r#"{"title":"the hobbit", "author":"JRR Tolkien", "genre": "Fantasy"}"#.into())
+        .await;
+    let other_author = app
+        .create_book(
+            r#"{"title":"The Hobbit", "author":"Christopher Tolkien", "genre": "Fiction"}"#.into(),
+        )
+        .await;
+
+    assert_eq!(duplicate.status().as_u16(), 409);
+    assert_eq!(
+        duplicate.headers()["Location"],
+        format!("/books/{}", original["id"].as_str().unwrap())
+    );
+    let body = duplicate
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    assert_eq!(body["book_id"], original["id"]);
+    assert!(other_author.status().is_success());
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn book_editions() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    let original = app
+        .create_book(r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction"}"#.into())
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let original_id = original["id"].as_str().unwrap();
+    let edition = app
+        .create_book(format!(
+            r#"{{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction", "parent_book_id": "{}"}}"#,
+            original_id
+        ))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let edition_id = edition["id"].as_str().unwrap();
+
+    let from_original = app
+        .book_editions(original_id)
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let from_edition = app
+        .book_editions(edition_id)
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let shown = app
+        .show_book(edition_id.to_string())
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(from_original["work_id"], original_id);
+    assert_eq!(from_original["editions"][0]["id"], edition_id);
+    assert_eq!(from_edition, from_original);
+    assert_eq!(shown["parent_book_id"], original_id);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn book_edition_parent_must_be_an_existing_original() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    let original = app
+        .create_book(r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction"}"#.into())
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let edition = app
+        .create_book(format!(
+            r#"{{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction", "parent_book_id": {}}}"#,
+            original["id"]
+        ))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    let grandchild = app
+        .create_book(format!(
+            r#"{{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction", "parent_book_id": {}}}"#,
+            edition["id"]
+        ))
+        .await;
+    let grandchild_status = grandchild.status().as_u16();
+    let grandchild_message = grandchild
+        .text()
+        .await
+        .expect("Failed to read response body.");
+    let orphan = app
+        .create_book(
+            r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction", "parent_book_id": "7c7b6f3e-1b1f-4a3e-9a3b-2f2d3b3c4d5e"}"#.into(),
+        )
+        .await;
+    let missing = app
+        .book_editions("7c7b6f3e-1b1f-4a3e-9a3b-2f2d3b3c4d5e")
+        .await;
+
+    assert_eq!(grandchild_status, 422);
+    assert!(grandchild_message.contains("is itself an edition"));
+    assert_eq!(orphan.status().as_u16(), 422);
+    assert_eq!(missing.status().as_u16(), 404);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn deleting_a_work_with_editions_is_a_conflict() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    let work = app
+        .create_book(r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction"}"#.into())
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let work_id = work["id"].as_str().unwrap();
+    let mut edition_ids = Vec::new();
+    for _ in 0..2 {
+        let edition = app
+            .create_book(format!(
+                r#"{{"title":"Rev Ed Zq", "author":"JRR Tolkien", "genre": "Fiction", "parent_book_id": "{}"}}"#,
+                work_id
+            ))
+            .await
+            .json::<Value>()
+            .await
+            .expect("Failed to deserialize response body.");
+        edition_ids.push(edition["id"].as_str().unwrap().to_string());
+    }
+
+    let refused = app.delete_book_by_id(work_id).await;
+    let refused_status = refused.status().as_u16();
+    let refused_body = refused
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    for edition_id in &edition_ids {
+        app.delete_book_by_id(edition_id).await;
+    }
+    let deleted = app.delete_book_by_id(work_id).await;
+
+    assert_eq!(refused_status, 409);
+    assert_eq!(refused_body["error"]["code"], "conflict");
+    assert!(refused_body["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("still has editions"));
+    assert_eq!(deleted.status().as_u16(), 200);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
 #[tokio::test]
 async fn book_creation_with_incomplete_data() {
     let app = spawn_app().await;
@@ -111,6 +500,69 @@ async fn book_creation_with_incomplete_data() {
     drop_db(app.db_name, app.db_url).await;
 }
 
+#[tokio::test]
+async fn book_validation_never_inserts() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+
+    let valid = app
+        .validate_book(
+            r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction"}"#.into(),
+        )
+        .await;
+    let record = sqlx::query!("SELECT * FROM books")
+        .fetch_optional(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved book.");
+
+    assert_eq!(valid.status().as_u16(), 200);
+    assert_eq!(
+        valid
+            .json::<Value>()
+            .await
+            .expect("Failed to deserialize response body.")["valid"],
+        true
+    );
+    assert!(record.is_none());
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn book_validation_reports_errors() {
+    let app = spawn_app().await;
+
+    let invalid_fields = app
+        .validate_book(r#"{"title":"", "author":"JRR Tolkien", "genre": " "}"#.into())
+        .await;
+    let unknown_author = app
+        .validate_book(
+            r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction"}"#.into(),
+        )
+        .await;
+
+    assert_eq!(invalid_fields.status().as_u16(), 400);
+    let body = invalid_fields
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    assert_eq!(body["valid"], false);
+    assert_eq!(body["errors"].as_array().unwrap().len(), 2);
+    assert_eq!(unknown_author.status().as_u16(), 400);
+    let body = unknown_author
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    assert_eq!(body["errors"][0]["code"], "author_not_found");
+    assert_eq!(
+        body["errors"][0]["message"],
+        "Author 'JRR Tolkien' does not exist."
+    );
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
 #[tokio::test]
 async fn book_deletion() {
     let app = spawn_app().await;
@@ -125,7 +577,7 @@ async fn book_deletion() {
         .json::<Value>()
         .await
         .expect("Failed to deserialize response body.");
-    let book_id = response_body["book_id"]
+    let book_id = response_body["id"]
         .as_str()
         .expect("Failed to extract author id from response.");
 
@@ -139,3 +591,448 @@ async fn book_deletion() {
 
     drop_db(app.db_name, app.db_url).await;
 }
+
+#[tokio::test]
+async fn book_deletion_by_id() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    let book_id = app
+        .create_book(
+            r#"{"title":"Lord of the Rings", "author":"JRR Tolkien", "genre": "Fiction"}"#.into(),
+        )
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.")["id"]
+        .as_str()
+        .expect("Failed to extract book id from response.")
+        .to_owned();
+
+    let response = app.delete_book_by_id(&book_id).await;
+    let missing = app.delete_book_by_id(&book_id).await;
+    let malformed = app.delete_book_by_id("not-a-uuid").await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(missing.status().as_u16(), 404);
+    assert_eq!(malformed.status().as_u16(), 400);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn books_export() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    app.create_book(
+        r#"{"title":"Lord of the Rings", "author":"JRR Tolkien", "genre": "Fiction"}"#.into(),
+    )
+    .await;
+
+    let response = app.export_books(None).await;
+
+    assert!(response.status().is_success());
+    assert!(response.headers().get("Content-Encoding").is_none());
+    let body = response
+        .text()
+        .await
+        .expect("Failed to read response body.");
+    let lines: Vec<&str> = body.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], "id,title,author,genre,created_at");
+    assert!(lines[1].contains(",Lord of the Rings,JRR Tolkien,Fiction,"));
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn books_export_with_range() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    app.create_book(
+        r#"{"title":"Lord of the Rings", "author":"JRR Tolkien", "genre": "Fiction"}"#.into(),
+    )
+    .await;
+
+    let full_response = app.export_books(None).await;
+    assert!(full_response.headers().get("Accept-Ranges").is_none());
+    let full = full_response
+        .text()
+        .await
+        .expect("Failed to read response body.");
+    let partial_response = app.export_books_range("bytes=10-").await;
+    let unsatisfiable = app.export_books_range("bytes=100000-").await;
+    let conditional = reqwest::Client::new()
+        .get(format!("http://{}/books/export", &app.address))
+        .header("Range", "bytes=10-")
+        .header("If-Range", "\"books\"")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(partial_response.status().as_u16(), 206);
+    assert_eq!(
+        partial_response.headers()["Content-Range"],
+        format!("bytes 10-{}/{}", full.len() - 1, full.len()).as_str()
+    );
+    let partial = partial_response
+        .text()
+        .await
+        .expect("Failed to read response body.");
+    assert_eq!(partial, full[10..]);
+    assert_eq!(unsatisfiable.status().as_u16(), 416);
+    assert_eq!(conditional.status().as_u16(), 200);
+    assert_eq!(
+        conditional
+            .text()
+            .await
+            .expect("Failed to read response body."),
+        full
+    );
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn books_export_with_gzip() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    app.create_book(
+        r#"{"title":"Lord of the Rings", "author":"JRR Tolkien", "genre": "Fiction"}"#.into(),
+    )
+    .await;
+    app.create_book(r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction"}"#.into())
+        .await;
+
+    let response = app.export_books(Some("gzip, deflate")).await;
+
+    assert!(response.status().is_success());
+    assert_eq!(response.headers()["Content-Encoding"], "gzip");
+    let compressed = response
+        .bytes()
+        .await
+        .expect("Failed to read response body.");
+    let mut body = String::new();
+    GzDecoder::new(&compressed[..])
+        .read_to_string(&mut body)
+        .expect("Failed to decompress response body.");
+    let lines: Vec<&str> = body.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "id,title,author,genre,created_at");
+    assert!(lines[1].contains(",Lord of the Rings,JRR Tolkien,Fiction,"));
+    assert!(lines[2].contains(",The Hobbit,JRR Tolkien,Fiction,"));
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn books_export_without_gzip_when_refused() {
+    let app = spawn_app().await;
+
+    let refused = app.export_books(Some("gzip;q=0, deflate")).await;
+    let weighted = app.export_books(Some("deflate, gzip;q=0.5")).await;
+
+    assert!(refused.status().is_success());
+    assert!(refused.headers().get("Content-Encoding").is_none());
+    assert_eq!(
+        refused.text().await.unwrap().lines().next(),
+        Some("id,title,author,genre,created_at")
+    );
+    assert_eq!(weighted.headers()["Content-Encoding"], "gzip");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn book_listings_honour_the_timezone() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    app.create_book(r#"{"title":"The Hobbit", "author":"JRR Tolkien", "genre": "Fiction"}"#.into())
+        .await;
+
+    let search = app
+        .search_books("q=hobbit&tz=Asia/Kolkata")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let random = app
+        .random_books("tz=Asia/Kolkata")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let export = app
+        .get("/books/export?tz=Asia/Kolkata")
+        .await
+        .text()
+        .await
+        .expect("Failed to read response body.");
+    let unknown = app.random_books("tz=Mars/Olympus_Mons").await;
+
+    assert!(search[0]["created_at"]
+        .as_str()
+        .unwrap()
+        .ends_with("+05:30"));
+    assert!(random[0]["created_at"]
+        .as_str()
+        .unwrap()
+        .ends_with("+05:30"));
+    assert!(export.lines().nth(1).unwrap().ends_with("+05:30"));
+    assert_eq!(unknown.status().as_u16(), 400);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn books_added_timeline() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await;
+    for title in ["Dune", "Dune Messiah", "Children of Dune"] {
+        app.create_book(format!(
+            r#"{{"title":"{}", "author":"Frank Herbert", "genre": "Sci-Fi"}}"#,
+            title
+        ))
+        .await;
+    }
+    sqlx::query!(
+        "UPDATE books SET created_at = CASE title
+            WHEN 'Children of Dune' THEN '2024-03-12T23:59:00Z'::timestamptz
+            ELSE '2024-03-10T08:00:00Z'::timestamptz
+        END"
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to backdate books.");
+
+    let sparse = app
+        .added_timeline("from=2024-03-10&to=2024-03-12")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let filled = app
+        .added_timeline("from=2024-03-10&to=2024-03-12&fill_gaps=true")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(
+        sparse,
+        serde_json::json!([
+            {"day": "2024-03-10", "books_count": 2},
+            {"day": "2024-03-12", "books_count": 1}
+        ])
+    );
+    assert_eq!(filled.as_array().unwrap().len(), 3);
+    assert_eq!(
+        filled[1],
+        serde_json::json!({"day": "2024-03-11", "books_count": 0})
+    );
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn books_added_timeline_rejects_bad_ranges() {
+    let app = spawn_app().await;
+
+    let reversed = app.added_timeline("from=2024-03-12&to=2024-03-10").await;
+    let too_long = app.added_timeline("from=2020-01-01&to=2024-01-01").await;
+    let malformed = app.added_timeline("from=yesterday").await;
+
+    assert_eq!(reversed.status().as_u16(), 400);
+    assert_eq!(too_long.status().as_u16(), 400);
+    assert_eq!(malformed.status().as_u16(), 400);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn show_book_with_invalid_uuid() {
+    let app = spawn_app().await;
+
+    let response = app.show_book("not-a-uuid".into()).await;
+    let legacy_delete = app.book_delete(r#"{"id": "not-a-uuid"}"#.into()).await;
+
+    assert_eq!(response.status().as_u16(), 400);
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    assert_eq!(body["error"]["code"], "invalid_uuid");
+    assert_eq!(
+        body["error"]["message"],
+        "'not-a-uuid' is not a valid UUID."
+    );
+    assert_eq!(legacy_delete.status().as_u16(), 400);
+    let body = legacy_delete
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    assert_eq!(body["error"]["code"], "invalid_uuid");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn book_import_preview_reports_each_row() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await;
+    let csv = "genre,title,author\n\
+        Sci-Fi,\"Dune, Part One\",Frank Herbert\n\
+        Fiction,The Hobbit,JRR Tolkien\n\
+        Sci-Fi,Dune Messiah\n\
+        Sci-Fi,Children of Dune,Frank Herbert\n";
+
+    let response = app.preview_book_import(csv).await;
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let saved = sqlx::query!("SELECT id FROM books")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved books.");
+
+    assert_eq!(body["summary"]["rows"], 4);
+    assert_eq!(body["summary"]["valid"], 2);
+    assert_eq!(body["summary"]["invalid"], 2);
+    assert_eq!(body["rows"][0]["valid"], true);
+    assert_eq!(body["rows"][1]["errors"][0]["code"], "author_not_found");
+    assert_eq!(body["rows"][2]["errors"][0]["code"], "malformed_row");
+    assert_eq!(body["rows"][3]["valid"], true);
+    assert!(saved.is_empty());
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn book_import_preview_requires_columns() {
+    let app = spawn_app().await;
+
+    let response = app.preview_book_import("title,genre\nDune,Sci-Fi\n").await;
+
+    assert_eq!(response.status().as_u16(), 400);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn book_creation_errors_are_json() {
+    let app = spawn_app().await;
+
+    let response = app
+        .create_book(r#"{"title":"", "author":"Nobody", "genre": ""}"#.into())
+        .await;
+
+    assert_eq!(response.status().as_u16(), 422);
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    assert_eq!(body["error"]["code"], "invalid_input");
+    assert_eq!(body["error"]["details"].as_array().unwrap().len(), 2);
+    assert_eq!(body["error"]["details"][0]["field"], "title");
+    assert_eq!(body["error"]["details"][1]["field"], "genre");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn search_books_ranks_title_matches_first() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    app.create_author(r#"{"name":"Humphrey Carpenter", "nationality":"British"}"#.into())
+        .await;
+    app.create_book(
+        r#"{"title":"The Lord of the Rings", "author":"JRR Tolkien", "genre": "Fiction"}"#.into(),
+    )
+    .await;
+    app.create_book(
+        r#"{"title":"Tolkien: A Biography", "author":"Humphrey Carpenter", "genre": "Biography"}"#
+            .into(),
+    )
+    .await;
+    app.create_book(
+        r#"{"title":"The Inklings", "author":"Humphrey Carpenter", "genre": "Biography"}"#.into(),
+    )
+    .await;
+
+    let by_name = app.search_books("q=Tolkien").await;
+    let total = by_name.headers()["X-Total-Count"].clone();
+    let by_name = by_name
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let stemmed = app
+        .search_books("q=ring")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let paged = app
+        .search_books("q=Tolkien&per_page=1&page=2")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let empty = app.search_books("q=%20").await;
+
+    assert_eq!(total, "2");
+    assert_eq!(by_name[0]["title"], "Tolkien: A Biography");
+    assert_eq!(by_name[1]["title"], "The Lord of the Rings");
+    assert!(by_name[0]["rank"].as_f64() > by_name[1]["rank"].as_f64());
+    assert_eq!(stemmed.as_array().unwrap().len(), 1);
+    assert_eq!(stemmed[0]["author"], "JRR Tolkien");
+    assert_eq!(paged.as_array().unwrap().len(), 1);
+    assert_eq!(paged[0]["title"], "The Lord of the Rings");
+    assert_eq!(empty.status().as_u16(), 400);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn book_creation_by_author_id() {
+    let app = spawn_app().await;
+    let author = app
+        .create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    let created = app
+        .create_book(format!(
+            r#"{{"title":"The Hobbit", "author_id":{}, "genre": "Fiction"}}"#,
+            author["id"]
+        ))
+        .await;
+    let created_status = created.status().as_u16();
+    let unknown = app
+        .create_book(
+            r#"{"title":"The Hobbit", "author_id":"7c7b6f3e-1b1f-4a3e-9a3b-2f2d3b3c4d5e", "genre": "Fiction"}"#.into(),
+        )
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let book = sqlx::query!("SELECT author_id FROM books")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved book.");
+
+    assert_eq!(created_status, 201);
+    assert_eq!(book.author_id.to_string(), author["id"]);
+    assert_eq!(unknown["error"]["details"][0]["code"], "author_not_found");
+
+    drop_db(app.db_name, app.db_url).await;
+}
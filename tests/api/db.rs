@@ -0,0 +1,117 @@
+use crate::test_helpers::{drop_db, spawn_app};
+use midnight_library::db::{begin, warm_up, TimedPool};
+use midnight_library::self_test::self_test;
+use sqlx::postgres::PgPoolOptions;
+use std::time::Duration;
+
+#[tokio::test]
+async fn warm_up_leaves_connections_idle() {
+    let app = spawn_app().await;
+    let pool = PgPoolOptions::new()
+        .max_connections(5)
+        .connect_lazy_with(app.db_pool.connect_options().as_ref().clone());
+
+    warm_up(&pool, 3, Duration::from_millis(10))
+        .await
+        .expect("Failed to warm up the pool.");
+
+    // Connections are handed back to the pool by a background task on drop.
+    for _ in 0..50 {
+        if pool.num_idle() == 3 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert_eq!(pool.size(), 3);
+    assert_eq!(pool.num_idle(), 3);
+
+    pool.close().await;
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn begin_hands_out_a_usable_transaction() {
+    let app = spawn_app().await;
+
+    let mut transaction = begin("test", &TimedPool::new(app.db_pool.clone()))
+        .await
+        .expect("Failed to begin transaction.");
+    let record = sqlx::query!(r#"SELECT 1 AS "one!""#)
+        .fetch_one(&mut transaction)
+        .await
+        .expect("Failed to run query.");
+    transaction
+        .rollback()
+        .await
+        .expect("Failed to roll back transaction.");
+
+    assert_eq!(record.one, 1);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn timed_pool_runs_queries_and_returns_connections() {
+    let app = spawn_app().await;
+    let pool = TimedPool::new(app.db_pool.clone());
+
+    let rows = sqlx::query!(r#"SELECT generate_series(1, 3) AS "n!""#)
+        .fetch_all(&pool)
+        .await
+        .expect("Failed to fetch rows.");
+    let row = sqlx::query!(r#"SELECT 1 AS "one!""#)
+        .fetch_optional(&pool)
+        .await
+        .expect("Failed to fetch row.");
+    let named = sqlx::query!(r#"SELECT 2 AS "two!""#)
+        .fetch_one(pool.named("test"))
+        .await
+        .expect("Failed to fetch row.");
+
+    assert_eq!(rows.len(), 3);
+    assert_eq!(row.map(|row| row.one), Some(1));
+    assert_eq!(named.two, 2);
+    for _ in 0..50 {
+        if pool.num_idle() as u32 == pool.size() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(pool.num_idle() as u32, pool.size());
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn self_test_passes_and_leaves_no_rows() {
+    let app = spawn_app().await;
+
+    let result = self_test(&TimedPool::new(app.db_pool.clone())).await;
+    let authors = sqlx::query!("SELECT id FROM authors")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved authors.");
+
+    assert_eq!(result, Ok(()));
+    assert!(authors.is_empty());
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn self_test_reports_failing_step() {
+    let app = spawn_app().await;
+    sqlx::query!("ALTER TABLE authors RENAME COLUMN nationality TO country")
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to break the schema.");
+
+    let result = self_test(&TimedPool::new(app.db_pool.clone())).await;
+
+    assert!(result
+        .unwrap_err()
+        .starts_with("Self-test failed at 'create'"));
+
+    drop_db(app.db_name, app.db_url).await;
+}
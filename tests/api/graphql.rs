@@ -0,0 +1,296 @@
+use crate::test_helpers::{drop_db, spawn_app};
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn graphql_creates_and_lists_authors_with_books() {
+    let app = spawn_app().await;
+
+    let created = app
+        .graphql(json!({
+            "query": r#"mutation { createAuthor(name: "Frank Herbert", nationality: "American") }"#
+        }))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    assert!(created["data"]["createAuthor"].is_string());
+    app.graphql(json!({
+        "query": r#"mutation { createBook(title: "Dune", author: "Frank Herbert", genre: "Sci-Fi") }"#
+    }))
+    .await;
+
+    let response = app
+        .graphql(json!({
+            "query": r#"{ authors(nationality: "American") { name books { title genre } } }"#
+        }))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(response["data"]["authors"][0]["name"], "Frank Herbert");
+    assert_eq!(response["data"]["authors"][0]["books"][0]["title"], "Dune");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn graphql_reports_validation_errors() {
+    let app = spawn_app().await;
+
+    let response = app
+        .graphql(json!({
+            "query": r#"mutation { createAuthor(name: "", nationality: "American") }"#
+        }))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(
+        response["errors"][0]["message"],
+        "'' is not a valid author name."
+    );
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn graphql_reports_duplicate_titles_as_conflicts() {
+    let app = spawn_app().await;
+    app.create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await;
+    app.create_book(r#"{"title":"Dune", "author":"Frank Herbert", "genre": "Sci-Fi"}"#.into())
+        .await;
+
+    let response = app
+        .graphql(json!({
+            "query": r#"mutation { createBook(title: "dune", author: "Frank Herbert", genre: "Sci-Fi") }"#
+        }))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(
+        response["errors"][0]["message"],
+        "This author already has a book with that title."
+    );
+    assert_eq!(response["errors"][0]["extensions"]["code"], "conflict");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn graphql_updates_authors_and_books_and_shows_users() {
+    let app = spawn_app().await;
+    let author_id = app
+        .graphql(json!({
+            "query": r#"mutation { createAuthor(name: "Frank Herbert", nationality: "American") }"#
+        }))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.")["data"]["createAuthor"]
+        .clone();
+    let book_id = app
+        .graphql(json!({
+            "query": r#"mutation { createBook(title: "Dune", author: "Frank Herbert", genre: "Sci-Fi") }"#
+        }))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.")["data"]["createBook"]
+        .clone();
+    let user_id = app
+        .graphql(json!({
+            "query": r#"mutation { createUser(name: "Ana", email: "ana@example.com") }"#
+        }))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.")["data"]["createUser"]
+        .clone();
+
+    let response = app
+        .graphql(json!({
+            "query": format!(
+                r#"mutation {{
+                    updateAuthor(id: {}, nationality: "British") {{ name nationality }}
+                    updateBook(id: {}, genre: "Science Fiction") {{ title genre }}
+                }}"#,
+                author_id, book_id
+            )
+        }))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let user = app
+        .graphql(json!({
+            "query": format!("{{ user(id: {}) {{ name email isActive }} }}", user_id)
+        }))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(
+        response["data"]["updateAuthor"],
+        json!({"name": "Frank Herbert", "nationality": "British"})
+    );
+    assert_eq!(
+        response["data"]["updateBook"],
+        json!({"title": "Dune", "genre": "Science Fiction"})
+    );
+    assert_eq!(
+        user["data"]["user"],
+        json!({"name": "Ana", "email": "ana@example.com", "isActive": true})
+    );
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn graphql_reports_constraint_violations_as_conflicts() {
+    let app = spawn_app().await;
+    let author_id = app
+        .create_author(r#"{"name":"Frank Herbert", "nationality":"American"}"#.into())
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.")["id"]
+        .clone();
+    app.create_book(r#"{"title":"Dune", "author":"Frank Herbert", "genre": "Sci-Fi"}"#.into())
+        .await;
+    app.create_user(r#"{"name":"Ana", "email":"ana@example.com"}"#.into())
+        .await;
+
+    let delete_author = app
+        .graphql(json!({
+            "query": format!("mutation {{ deleteAuthor(id: {}) }}", author_id)
+        }))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let duplicate_email = app
+        .graphql(json!({
+            "query": r#"mutation { createUser(name: "Ana", email: "ana@example.com") }"#
+        }))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(delete_author["errors"][0]["extensions"]["code"], "conflict");
+    assert_eq!(
+        duplicate_email["errors"][0]["message"],
+        "A user with this email already exists."
+    );
+    assert_eq!(
+        duplicate_email["errors"][0]["extensions"]["code"],
+        "conflict"
+    );
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn graphql_lists_are_paginated() {
+    let app = spawn_app().await;
+    for author in ["Frank Herbert", "Ursula K. Le Guin"] {
+        app.create_author(format!(
+            r#"{{"name":"{}", "nationality":"American"}}"#,
+            author
+        ))
+        .await;
+    }
+    for title in ["Dune", "Dune Messiah", "Children of Dune"] {
+        app.create_book(format!(
+            r#"{{"title":"{}", "author":"Frank Herbert", "genre": "Sci-Fi"}}"#,
+            title
+        ))
+        .await;
+    }
+
+    let response = app
+        .graphql(json!({
+            "query": r#"{
+                authors(perPage: 1) { name books(page: 2, perPage: 2) { title } }
+                books(page: 3, perPage: 1) { title }
+            }"#
+        }))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let invalid = app
+        .graphql(json!({"query": "{ authors(page: 0) { name } }"}))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(response["data"]["authors"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        response["data"]["authors"][0]["books"],
+        json!([{"title": "Children of Dune"}])
+    );
+    assert_eq!(
+        response["data"]["books"],
+        json!([{"title": "Children of Dune"}])
+    );
+    assert_eq!(invalid["errors"][0]["extensions"]["code"], "bad_request");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn graphql_hides_database_errors() {
+    let app = spawn_app().await;
+    sqlx::query("ALTER TABLE authors RENAME TO authors_elsewhere")
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to rename table.");
+
+    let response = app
+        .graphql(json!({"query": "{ authors { name } }"}))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(
+        response["errors"][0]["message"],
+        "Something went wrong on our side."
+    );
+    assert_eq!(
+        response["errors"][0]["extensions"]["code"],
+        "internal_error"
+    );
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn graphql_rejects_overly_deep_queries() {
+    let app = spawn_app().await;
+
+    let response = app
+        .graphql(json!({
+            "query": "{ __schema { types { fields { type { ofType { ofType { ofType { ofType { ofType { ofType { ofType { ofType { ofType { ofType { ofType { ofType { name } } } } } } } } } } } } } } } } }"
+        }))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert!(response["data"].is_null());
+    assert!(response["errors"][0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("too deep"));
+
+    drop_db(app.db_name, app.db_url).await;
+}
@@ -1,4 +1,5 @@
-use crate::test_helpers::{drop_db, spawn_app};
+use crate::test_helpers::{drop_db, spawn_app, spawn_app_with};
+use serde_json::Value;
 
 #[tokio::test]
 async fn health_check() {
@@ -16,3 +17,276 @@ async fn health_check() {
 
     drop_db(app.db_name, app.db_url).await;
 }
+
+#[tokio::test]
+async fn unsupported_method_returns_allow_header() {
+    let app = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .delete(format!("http://{}/authors", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 405);
+    assert_eq!(response.headers()["Allow"], "GET, POST");
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    assert_eq!(body["error"]["code"], "method_not_allowed");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn disabled_feature_routes_are_not_registered() {
+    let app = spawn_app_with(|config| config.features.set("seeding", false)).await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(format!("http://{}/seed_authors", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 404);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn disabled_feature_routes_do_not_fall_through_to_id_routes() {
+    let app = spawn_app_with(|config| {
+        config.features.set("export", false);
+        config.features.set("search", false);
+    })
+    .await;
+
+    let export = app.export_books(None).await;
+    let export_status = export.status().as_u16();
+    let export_body = export
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let search = app.search_authors("q=tolkien").await;
+    let index = app
+        .get("/")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(export_status, 404);
+    assert_eq!(export_body["error"]["code"], "not_found");
+    assert_eq!(search.status().as_u16(), 404);
+    assert!(!index
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|endpoint| endpoint["path"] == "/books/export"));
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn legacy_routes_are_deprecated_and_can_be_switched_off() {
+    let client = reqwest::Client::new();
+    let body = r#"{"name":"JRR Tolkien", "nationality":"British"}"#;
+
+    let app = spawn_app().await;
+    let response = client
+        .post(format!("http://{}/authors/create", app.address))
+        .header("Content-Type", "application/json")
+        .body(body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 201);
+    assert_eq!(response.headers()["Deprecation"], "true");
+    assert!(response.headers().contains_key("Sunset"));
+    drop_db(app.db_name, app.db_url).await;
+
+    let app = spawn_app_with(|config| config.features.set("legacy_routes", false)).await;
+    let response = client
+        .post(format!("http://{}/authors/create", app.address))
+        .header("Content-Type", "application/json")
+        .body(body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Now only matches `/authors/{author_id}`, which takes no POST.
+    assert_eq!(response.status().as_u16(), 405);
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn rate_limited_clients_get_429_with_retry_after() {
+    let app = spawn_app_with(|config| {
+        config.rate_limit.enabled = true;
+        config.rate_limit.requests_per_second = 0.1;
+        config.rate_limit.burst = 1;
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let get = |path: &str| client.get(format!("http://{}{}", app.address, path)).send();
+
+    let first = get("/authors").await.expect("Failed to execute request.");
+    let second = get("/authors").await.expect("Failed to execute request.");
+    let health = get("/health_check")
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(first.status().as_u16(), 200);
+    assert_eq!(second.status().as_u16(), 429);
+    assert_eq!(second.headers()["Retry-After"], "10");
+    assert_eq!(health.status().as_u16(), 200);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn unknown_route_returns_json_not_found() {
+    let app = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(format!("http://{}/definitely/not/a/route", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let status = response.status().as_u16();
+    let body = response
+        .json::<serde_json::Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(status, 404);
+    assert_eq!(body["error"]["code"], "not_found");
+    assert_eq!(body["error"]["message"], "route not found");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn malformed_json_returns_json_bad_request() {
+    let app = spawn_app().await;
+
+    let response = app.create_author(r#"{"name": "Ana""#.into()).await;
+    let status = response.status().as_u16();
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(status, 400);
+    assert_eq!(body["error"]["code"], "bad_request");
+    assert!(body["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("EOF while parsing"));
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn malformed_query_params_return_json_bad_request() {
+    let app = spawn_app().await;
+
+    let bad_page = app.author_index_with_query("page=abc").await;
+    let bad_page_status = bad_page.status().as_u16();
+    let bad_page_body = bad_page
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let missing_term = app.search_authors("").await;
+    let missing_term_status = missing_term.status().as_u16();
+    let missing_term_body = missing_term
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(bad_page_status, 400);
+    assert_eq!(bad_page_body["error"]["code"], "bad_request");
+    assert_eq!(missing_term_status, 400);
+    assert_eq!(missing_term_body["error"]["code"], "bad_request");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn api_index_lists_registered_endpoints() {
+    let app = spawn_app_with(|config| config.features.set("seeding", false)).await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(format!("http://{}/", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let body = response
+        .json::<serde_json::Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let endpoints = body.as_array().expect("Index should be an array.");
+
+    assert!(endpoints
+        .iter()
+        .any(|endpoint| endpoint["path"] == "/books" && endpoint["method"] == "POST"));
+    assert!(!endpoints
+        .iter()
+        .any(|endpoint| endpoint["path"] == "/seed_authors"));
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn body_log_passes_bodies_through() {
+    let app = spawn_app_with(|config| {
+        config.debug_body_log.enabled = true;
+        config.debug_body_log.max_bytes = 8;
+    })
+    .await;
+
+    let created = app
+        .create_author(r#"{"name":"JRR Tolkien", "nationality":"British"}"#.into())
+        .await;
+    let listed = app
+        .author_index()
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert!(created.status().is_success());
+    assert_eq!(listed[0]["name"], "JRR Tolkien");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn every_response_carries_its_own_request_id() {
+    let app = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let mut ids = Vec::new();
+    for _ in 0..2 {
+        let response = client
+            .get(format!("http://{}/health_check", app.address))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        let id = response.headers()["X-Request-Id"]
+            .to_str()
+            .unwrap()
+            .to_owned();
+        assert!(uuid::Uuid::parse_str(&id).is_ok());
+        ids.push(id);
+    }
+    assert_ne!(ids[0], ids[1]);
+
+    drop_db(app.db_name, app.db_url).await;
+}
@@ -1,4 +1,8 @@
+pub mod admin;
 pub mod authors;
 pub mod books;
+pub mod db;
+pub mod graphql;
+pub mod health_check;
 pub mod test_helpers;
 pub mod users;
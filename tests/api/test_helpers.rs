@@ -3,6 +3,8 @@ use sqlx::{Connection, Executor, PgConnection, PgPool};
 use std::net::TcpListener;
 use uuid::Uuid;
 
+pub const ADMIN_TOKEN: &str = "test-admin-token";
+
 pub struct TestApp {
     pub address: String,
     pub db_pool: PgPool,
@@ -11,15 +13,24 @@ pub struct TestApp {
 }
 
 pub async fn spawn_app() -> TestApp {
+    spawn_app_with(|_| {}).await
+}
+
+pub async fn spawn_app_with(
+    customize: impl FnOnce(&mut configuration::ApplicationConfigs),
+) -> TestApp {
     let tcp_listener = TcpListener::bind("localhost:0").expect("Failed to bind random port");
     let address = tcp_listener
         .local_addr()
         .expect("Failed to get local address")
         .to_string();
 
-    let (db_pool, db_name, db_url) = setup_db().await;
+    let mut config = configuration::get_configuration().expect("Failed to read configuration.");
+    config.admin_token = Some(ADMIN_TOKEN.to_string());
+    customize(&mut config);
+    let (db_pool, db_name, db_url) = setup_db(&config).await;
 
-    let server = run(tcp_listener, db_pool.clone()).expect("Failed to bind address");
+    let server = run(tcp_listener, db_pool.clone(), None, &config).expect("Failed to bind address");
     tokio::spawn(server);
 
     TestApp {
@@ -30,8 +41,7 @@ pub async fn spawn_app() -> TestApp {
     }
 }
 
-async fn setup_db() -> (PgPool, String, String) {
-    let config = configuration::get_configuration().expect("Failed to read configuration.");
+async fn setup_db(config: &configuration::ApplicationConfigs) -> (PgPool, String, String) {
     let db_url = format!(
         "postgres://{}:{}@{}:{}",
         config.database.username,
@@ -90,7 +100,66 @@ pub async fn drop_db(name: String, db_url: String) {
 impl TestApp {
     pub async fn create_author(&self, body: String) -> reqwest::Response {
         reqwest::Client::new()
-            .post(format!("http://{}/authors/create", &self.address))
+            .post(format!("http://{}/authors", &self.address))
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn create_author_in(&self, body: String, language: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("http://{}/authors", &self.address))
+            .header("Content-Type", "application/json")
+            .header("Accept-Language", language)
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn create_author_with_key(&self, body: &str, key: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("http://{}/authors", &self.address))
+            .header("Content-Type", "application/json")
+            .header("Idempotency-Key", key)
+            .body(body.to_string())
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn add_author_alias(&self, author_id: &str, body: String) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!(
+                "http://{}/authors/{}/aliases",
+                &self.address, author_id
+            ))
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn remove_author_alias(&self, author_id: &str, alias_id: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .delete(format!(
+                "http://{}/authors/{}/aliases/{}",
+                &self.address, author_id, alias_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn bulk_create_authors(&self, body: String, query: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!(
+                "http://{}/authors/bulk_create?{}",
+                &self.address, query
+            ))
             .header("Content-Type", "application/json")
             .body(body)
             .send()
@@ -98,6 +167,17 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn bulk_create_authors_as_csv(&self, body: String) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("http://{}/authors/bulk_create", &self.address))
+            .header("Content-Type", "application/json")
+            .header("Accept", "text/csv")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn delete_author(&self, body: String) -> reqwest::Response {
         reqwest::Client::new()
             .post(format!("http://{}/authors/delete", &self.address))
@@ -108,6 +188,42 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn update_author(
+        &self,
+        author_id: &str,
+        body: serde_json::Value,
+    ) -> reqwest::Response {
+        reqwest::Client::new()
+            .put(format!("http://{}/authors/{}", &self.address, author_id))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_author_by_id(&self, author_id: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .delete(format!("http://{}/authors/{}", &self.address, author_id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_author_by_id_with_query(
+        &self,
+        author_id: &str,
+        query: &str,
+    ) -> reqwest::Response {
+        reqwest::Client::new()
+            .delete(format!(
+                "http://{}/authors/{}?{}",
+                &self.address, author_id, query
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn author_index(&self) -> reqwest::Response {
         reqwest::Client::new()
             .get(format!("http://{}/authors", &self.address))
@@ -116,6 +232,58 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn author_index_with_query(&self, query: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("http://{}/authors?{}", &self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn search_authors(&self, query: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("http://{}/authors/search?{}", &self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn author_exists(&self, name: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("http://{}/authors/exists", &self.address))
+            .query(&[("name", name)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn author_by_name(&self, name: &str) -> reqwest::Response {
+        let mut url = reqwest::Url::parse(&format!("http://{}/authors/by-name/", &self.address))
+            .expect("Failed to parse url.");
+        url.path_segments_mut()
+            .expect("Failed to build url.")
+            .pop_if_empty()
+            .push(name);
+        reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("Failed to build client.")
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn batch_authors(&self, body: String) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("http://{}/authors/batch", &self.address))
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn show_author(&self, author_id: String) -> reqwest::Response {
         reqwest::Client::new()
             .get(format!("http://{}/authors/{}", &self.address, author_id))
@@ -124,9 +292,84 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn export_author(&self, author_id: &str, query: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!(
+                "http://{}/authors/{}/export?{}",
+                &self.address, author_id, query
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn author_books(&self, author_id: &str, query: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!(
+                "http://{}/authors/{}/books?{}",
+                &self.address, author_id, query
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn similar_authors(&self, author_id: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!(
+                "http://{}/authors/{}/similar",
+                &self.address, author_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn author_book_counts(&self, query: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!(
+                "http://{}/authors/book-counts?{}",
+                &self.address, query
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn nationality_stats(&self) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("http://{}/nationalities/stats", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn transfer_books(&self, author_id: &str, body: String) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!(
+                "http://{}/authors/{}/transfer_books",
+                &self.address, author_id
+            ))
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn create_book(&self, body: String) -> reqwest::Response {
         reqwest::Client::new()
-            .post(format!("http://{}/books/create", &self.address))
+            .post(format!("http://{}/books", &self.address))
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn validate_book(&self, body: String) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("http://{}/books/validate", &self.address))
             .header("Content-Type", "application/json")
             .body(body)
             .send()
@@ -142,6 +385,39 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn book_index_with_query(&self, query: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("http://{}/books?{}", &self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn search_books(&self, query: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("http://{}/books/search?{}", &self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn book_index_as_ndjson(&self) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("http://{}/books", &self.address))
+            .header("Accept", "application/x-ndjson")
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn random_books(&self, query: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("http://{}/books/random?{}", &self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn show_book(&self, book_id: String) -> reqwest::Response {
         reqwest::Client::new()
             .get(format!("http://{}/books/{}", &self.address, book_id))
@@ -150,6 +426,48 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn book_editions(&self, book_id: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!(
+                "http://{}/books/{}/editions",
+                &self.address, book_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn show_book_if_modified_since(
+        &self,
+        book_id: &str,
+        if_modified_since: &str,
+    ) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("http://{}/books/{}", &self.address, book_id))
+            .header("If-Modified-Since", if_modified_since)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn export_books(&self, accept_encoding: Option<&str>) -> reqwest::Response {
+        let mut request =
+            reqwest::Client::new().get(format!("http://{}/books/export", &self.address));
+        if let Some(encoding) = accept_encoding {
+            request = request.header("Accept-Encoding", encoding);
+        }
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn export_books_range(&self, range: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("http://{}/books/export", &self.address))
+            .header("Range", range)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn book_delete(&self, body: String) -> reqwest::Response {
         reqwest::Client::new()
             .post(format!("http://{}/books/delete", &self.address))
@@ -160,13 +478,146 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn delete_book_by_id(&self, book_id: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .delete(format!("http://{}/books/{}", &self.address, book_id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn set_maintenance_mode(&self, enabled: bool) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("http://{}/admin/maintenance", &self.address))
+            .header("Content-Type", "application/json")
+            .body(format!(r#"{{"enabled": {}}}"#, enabled))
+            .bearer_auth(ADMIN_TOKEN)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn reassign_genre(&self, body: String, query: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!(
+                "http://{}/admin/books/reassign_genre?{}",
+                &self.address, query
+            ))
+            .header("Content-Type", "application/json")
+            .body(body)
+            .bearer_auth(ADMIN_TOKEN)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn tag_books(&self, body: serde_json::Value, query: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!(
+                "http://{}/admin/books/tag?{}",
+                &self.address, query
+            ))
+            .json(&body)
+            .bearer_auth(ADMIN_TOKEN)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn set_featured_books(&self, body: serde_json::Value) -> reqwest::Response {
+        reqwest::Client::new()
+            .put(format!("http://{}/admin/books/featured", &self.address))
+            .json(&body)
+            .bearer_auth(ADMIN_TOKEN)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn featured_books(&self) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("http://{}/books/featured", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn backup(&self) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("http://{}/admin/backup", &self.address))
+            .bearer_auth(ADMIN_TOKEN)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn restore(&self, body: String, query: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("http://{}/admin/restore?{}", &self.address, query))
+            .header("Content-Type", "application/json")
+            .body(body)
+            .bearer_auth(ADMIN_TOKEN)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn preview_book_import(&self, csv: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("http://{}/books/import/preview", &self.address))
+            .header("Content-Type", "text/csv")
+            .body(csv.to_string())
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn added_timeline(&self, query: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!(
+                "http://{}/books/added-timeline?{}",
+                &self.address, query
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn graphql(&self, body: serde_json::Value) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("http://{}/graphql", &self.address))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn create_user(&self, body: String) -> reqwest::Response {
         reqwest::Client::new()
-            .post(format!("http://{}/users/create", &self.address))
+            .post(format!("http://{}/users", &self.address))
             .header("Content-Type", "application/json")
             .body(body)
             .send()
             .await
             .expect("Failed to execute request.")
     }
+
+    pub async fn get(&self, path: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("http://{}{}", &self.address, path))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn set_user_active(&self, user_id: &str, action: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!(
+                "http://{}/users/{}/{}",
+                &self.address, user_id, action
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
 }
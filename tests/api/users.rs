@@ -1,4 +1,5 @@
 use crate::test_helpers::{drop_db, spawn_app};
+use serde_json::Value;
 
 #[tokio::test]
 async fn user_creation() {
@@ -19,6 +20,42 @@ async fn user_creation() {
     drop_db(app.db_name, app.db_url).await;
 }
 
+#[tokio::test]
+async fn created_user_is_returned_with_its_location() {
+    let app = spawn_app().await;
+
+    let response = app
+        .create_user(r#"{"name":"Richard", "email":"example@email.com"}"#.into())
+        .await;
+    let status = response.status().as_u16();
+    let location = response.headers()["Location"]
+        .to_str()
+        .expect("Failed to read Location header.")
+        .to_string();
+    let created = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let shown = app
+        .get(&location)
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+
+    assert_eq!(status, 201);
+    assert_eq!(
+        location,
+        format!("/users/{}", created["id"].as_str().unwrap())
+    );
+    assert_eq!(created["name"], "Richard");
+    assert_eq!(created["email"], "example@email.com");
+    assert_eq!(created["is_active"], true);
+    assert_eq!(shown, created);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
 #[tokio::test]
 async fn user_creation_with_invalid_data() {
     let app = spawn_app().await;
@@ -36,3 +73,90 @@ async fn user_creation_with_invalid_data() {
 
     drop_db(app.db_name, app.db_url).await;
 }
+
+#[tokio::test]
+async fn user_creation_reports_each_invalid_field() {
+    let app = spawn_app().await;
+
+    let response = app
+        .create_user(r#"{"name":" ", "email":"example.com"}"#.into())
+        .await;
+
+    assert_eq!(response.status().as_u16(), 422);
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    let fields: Vec<&Value> = body["error"]["details"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|detail| &detail["field"])
+        .collect();
+    assert_eq!(fields, ["name", "email"]);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn user_creation_with_a_taken_email() {
+    let app = spawn_app().await;
+    let body = r#"{"name":"Richard", "email":"example@email.com"}"#;
+
+    app.create_user(body.into()).await;
+    let response = app.create_user(body.into()).await;
+
+    assert_eq!(response.status().as_u16(), 409);
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.");
+    assert_eq!(body["error"]["code"], "conflict");
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn user_deactivation_and_reactivation() {
+    let app = spawn_app().await;
+    let user_id = app
+        .create_user(r#"{"name":"Richard", "email":"example@email.com"}"#.into())
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize response body.")["id"]
+        .as_str()
+        .expect("Failed to extract user id from response.")
+        .to_string();
+
+    let deactivated = app.set_user_active(&user_id, "deactivate").await;
+    let record = sqlx::query!("SELECT is_active FROM users")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved user.");
+    assert_eq!(deactivated.status().as_u16(), 200);
+    assert!(!record.is_active);
+
+    let reactivated = app.set_user_active(&user_id, "reactivate").await;
+    let record = sqlx::query!("SELECT is_active FROM users")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved user.");
+    assert_eq!(reactivated.status().as_u16(), 200);
+    assert!(record.is_active);
+
+    drop_db(app.db_name, app.db_url).await;
+}
+
+#[tokio::test]
+async fn deactivating_a_missing_user() {
+    let app = spawn_app().await;
+
+    let response = app
+        .set_user_active("7c7b6f3e-1b1f-4a3e-9a3b-2f2d3b3c4d5e", "deactivate")
+        .await;
+
+    assert_eq!(response.status().as_u16(), 404);
+
+    drop_db(app.db_name, app.db_url).await;
+}